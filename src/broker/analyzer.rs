@@ -1,25 +1,105 @@
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
+use crate::ta::rolling::RollingSum;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
-#[derive(Clone)] // for the #[pyo3(get)]
+// china average trading days in a year
+pub const CN_TRADING_DAYS: f64 = 243.0;
+// china stock market session minutes in a day, 09:30-11:30 and 13:00-15:00
+pub const CN_SESSION_MINUTES: f64 = 240.0;
+
+#[pyclass(module = "bktrader.broker")]
+#[derive(Clone, Serialize, Deserialize)] // for the #[pyo3(get)]
 pub struct Analyzer {
     #[pyo3(get)]
-    equity_curve: Vec<f64>,
+    pub dts: Vec<i64>,
+    #[pyo3(get)]
+    pub equity_curve: Vec<f64>,
+    #[pyo3(get)]
+    pub cash_curve: Vec<f64>,
+    // market value of active positions
+    #[pyo3(get)]
+    pub invested_curve: Vec<f64>,
+    // NaN while the portfolio value is zero
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
+    pub gross_exposure: Vec<f64>,
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
+    pub net_exposure: Vec<f64>,
+    #[pyo3(get)]
+    pub active_counts: Vec<usize>,
+    // traded amount of entries & exits in each bar
+    #[pyo3(get)]
+    pub turnover_curve: Vec<f64>,
+    // bars per year, used to annualize all metrics
+    #[pyo3(get)]
+    pub periods_per_year: f64,
 }
 
 #[pymethods]
 impl Analyzer {
     #[new]
-    pub fn new() -> Self {
+    #[pyo3(signature = (periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(periods_per_year: f64) -> Self {
         Analyzer {
+            dts: Vec::with_capacity(1024),
             equity_curve: Vec::with_capacity(1024),
+            cash_curve: Vec::with_capacity(1024),
+            invested_curve: Vec::with_capacity(1024),
+            gross_exposure: Vec::with_capacity(1024),
+            net_exposure: Vec::with_capacity(1024),
+            active_counts: Vec::with_capacity(1024),
+            turnover_curve: Vec::with_capacity(1024),
+            periods_per_year,
         }
     }
 
-    pub fn update(&mut self, portfolio_value: f64) {
+    // bars per year derived from a session calendar
+    // china daily bar: (243, 240, 240); china 1-minute bar: (243, 240, 1); 24/7 1-hour bar: (365, 1440, 60)
+    #[staticmethod]
+    #[pyo3(signature = (trading_days=CN_TRADING_DAYS, session_minutes=CN_SESSION_MINUTES, bar_minutes=CN_SESSION_MINUTES))]
+    pub fn session_periods(trading_days: f64, session_minutes: f64, bar_minutes: f64) -> f64 {
+        trading_days * (session_minutes / bar_minutes).ceil()
+    }
+
+    pub fn update(&mut self, dt: i64, portfolio_value: f64) {
+        self.dts.push(dt);
         self.equity_curve.push(portfolio_value);
     }
 
+    // called after update() in the same bar, exposures are relative to the latest portfolio value
+    pub fn update_exposure(&mut self, cash: f64, long_value: f64, short_value: f64, active_count: usize, traded_amount: f64) {
+        let portfolio_value = self.equity_curve.last().copied().unwrap_or(f64::NAN);
+        self.cash_curve.push(cash);
+        self.invested_curve.push(long_value - short_value);
+        self.gross_exposure.push((long_value + short_value) / portfolio_value);
+        self.net_exposure.push((long_value - short_value) / portfolio_value);
+        self.active_counts.push(active_count);
+        self.turnover_curve.push(traded_amount);
+    }
+
+    pub fn avg_exposure(&self) -> f64 {
+        self.gross_exposure.iter().sum::<f64>() / self.gross_exposure.len() as f64
+    }
+
+    pub fn avg_net_exposure(&self) -> f64 {
+        self.net_exposure.iter().sum::<f64>() / self.net_exposure.len() as f64
+    }
+
+    // fraction of bars holding at least one active position
+    pub fn time_in_market(&self) -> f64 {
+        self.active_counts.iter().filter(|&&count| count > 0).count() as f64 / self.active_counts.len() as f64
+    }
+
+    // annualized traded amount over average portfolio value
+    pub fn annual_turnover(&self) -> f64 {
+        let bars = self.turnover_curve.len() as f64;
+        let avg_portfolio_value = self.equity_curve.iter().sum::<f64>() / self.equity_curve.len() as f64;
+        self.turnover_curve.iter().sum::<f64>() / avg_portfolio_value / bars * self.periods_per_year
+    }
+
     pub fn total_return(&self) -> f64 {
         if let (Some(&first), Some(&last)) = (self.equity_curve.first(), self.equity_curve.last()) {
             (last - first) / first
@@ -31,33 +111,16 @@ impl Analyzer {
     // CAGR (Compound Annual Growth Rate)
     pub fn cagr(&self) -> f64 {
         let total_return = self.total_return();
-        let trading_days = 243.0; // china average trading days in a year
-        let periods = self.equity_curve.len() as f64 / trading_days;
-        let cagr = (1.0 + total_return).powf(1.0 / periods) - 1.0;
-
-        cagr
+        let periods = self.equity_curve.len() as f64 / self.periods_per_year;
+        (1.0 + total_return).powf(1.0 / periods) - 1.0
     }
 
     pub fn max_drawdown(&self) -> f64 {
-        let mut running_max = std::f64::NEG_INFINITY;
-        let mut max_drawdown = 0.0;
-
-        for &portfolio_value in self.equity_curve.iter() {
-            if portfolio_value > running_max {
-                running_max = portfolio_value;
-            } else {
-                let drawdown = (running_max - portfolio_value) / running_max;
-                if drawdown > max_drawdown {
-                    max_drawdown = drawdown;
-                }
-            }
-        }
-
-        max_drawdown
+        max_drawdown_of(&self.equity_curve)
     }
 
     pub fn max_drawup(&self) -> f64 {
-        let mut running_min = std::f64::INFINITY;
+        let mut running_min = f64::INFINITY;
         let mut max_drawup = 0.0;
 
         for &portfolio_value in self.equity_curve.iter() {
@@ -91,7 +154,7 @@ impl Analyzer {
         let avg_daily_return = sum_returns / daily_returns.len() as f64;
 
         // Annualize the average daily return
-        let trading_days = 243.0; // Average trading days in a year of china
+        let trading_days = self.periods_per_year;
         let annual_return = (1.0 + avg_daily_return).powf(trading_days) - 1.0;
 
         // Step 3: Calculate Daily Return Volatility (Standard Deviation)
@@ -129,7 +192,7 @@ impl Analyzer {
         let avg_daily_return = sum_returns / daily_returns.len() as f64;
 
         // Annualize the average daily return
-        let trading_days = 243.0; // Average trading days in a year
+        let trading_days = self.periods_per_year;
         let annual_return = (1.0 + avg_daily_return).powf(trading_days) - 1.0;
 
         // Step 3: Calculate Downside Deviation
@@ -162,4 +225,360 @@ impl Analyzer {
 
         (annual_return, annual_downside_deviation, sortino_ratio)
     }
+
+    // rolling metrics below are aligned with dts & equity_curve, NAN before the window is filled
+    pub fn rolling_volatility(&self, window: usize) -> Result<Vec<f64>> {
+        check_window(window)?;
+        let mut sumer = RollingSum::new(window)?;
+        let mut sq_sumer = RollingSum::new(window)?;
+        let n = window as f64;
+        Ok(self
+            .returns()
+            .into_iter()
+            .map(|r| {
+                let sum = sumer.update(r);
+                let sq_sum = sq_sumer.update(r * r);
+                let variance = (sq_sum - sum * sum / n) / (n - 1.0);
+                variance.max(0.0).sqrt() * self.periods_per_year.sqrt()
+            })
+            .collect())
+    }
+
+    pub fn rolling_sharpe(&self, window: usize, risk_free_rate: f64) -> Result<Vec<f64>> {
+        check_window(window)?;
+        let mut sumer = RollingSum::new(window)?;
+        let mut sq_sumer = RollingSum::new(window)?;
+        let n = window as f64;
+        Ok(self
+            .returns()
+            .into_iter()
+            .map(|r| {
+                let sum = sumer.update(r);
+                let sq_sum = sq_sumer.update(r * r);
+                let annual_return = (1.0 + sum / n).powf(self.periods_per_year) - 1.0;
+                let variance = (sq_sum - sum * sum / n) / (n - 1.0);
+                let annual_volatility = variance.max(0.0).sqrt() * self.periods_per_year.sqrt();
+                if annual_volatility != 0.0 {
+                    (annual_return - risk_free_rate) / annual_volatility
+                } else {
+                    0.0
+                }
+            })
+            .collect())
+    }
+
+    pub fn rolling_sortino(&self, window: usize, risk_free_rate: f64, mar: f64) -> Result<Vec<f64>> {
+        check_window(window)?;
+        let mut sumer = RollingSum::new(window)?;
+        let mut downside_sq_sumer = RollingSum::new(window)?;
+        let mut downside_counter = RollingSum::new(window)?;
+        let n = window as f64;
+        Ok(self
+            .returns()
+            .into_iter()
+            .map(|r| {
+                let sum = sumer.update(r);
+                // a non-finite return (the first one, or after a zero portfolio value) goes into every sumer as NAN,
+                // so it is never counted as downside and the windows holding it stay NAN together
+                let deviation = if r.is_finite() { (r - mar / self.periods_per_year).min(0.0) } else { f64::NAN };
+                let downside_sq_sum = downside_sq_sumer.update(deviation * deviation);
+                let downside_count = downside_counter.update(if deviation.is_nan() { f64::NAN } else if deviation < 0.0 { 1.0 } else { 0.0 });
+                let annual_return = (1.0 + sum / n).powf(self.periods_per_year) - 1.0;
+                let downside_variance = if downside_count > 0.0 { downside_sq_sum / downside_count } else { 0.0 };
+                let annual_downside_deviation = downside_variance.sqrt() * self.periods_per_year.sqrt();
+                if sum.is_nan() {
+                    f64::NAN
+                } else if annual_downside_deviation != 0.0 {
+                    (annual_return - risk_free_rate) / annual_downside_deviation
+                } else {
+                    0.0
+                }
+            })
+            .collect())
+    }
+
+    pub fn rolling_max_drawdown(&self, window: usize) -> Result<Vec<f64>> {
+        check_window(window)?;
+        Ok((0..self.equity_curve.len())
+            .map(|i| if i + 1 < window { f64::NAN } else { max_drawdown_of(&self.equity_curve[i + 1 - window..=i]) })
+            .collect())
+    }
+
+    // benchmark: prices or portfolio values aligned with equity_curve
+    pub fn rolling_beta(&self, benchmark: Vec<f64>, window: usize) -> Result<Vec<f64>> {
+        check_window(window)?;
+        if benchmark.len() != self.equity_curve.len() {
+            return Err(Error::InvalidParameter(format!("benchmark must have one value per bar {}, got {}", self.equity_curve.len(), benchmark.len())));
+        }
+        let mut xy_sumer = RollingSum::new(window)?;
+        let mut x_sumer = RollingSum::new(window)?;
+        let mut y_sumer = RollingSum::new(window)?;
+        let mut xx_sumer = RollingSum::new(window)?;
+        let n = window as f64;
+        Ok(self
+            .returns()
+            .into_iter()
+            .zip(returns_of(&benchmark))
+            .map(|(y, x)| {
+                let xy_sum = xy_sumer.update(x * y);
+                let x_sum = x_sumer.update(x);
+                let y_sum = y_sumer.update(y);
+                let xx_sum = xx_sumer.update(x * x);
+                (xy_sum - x_sum * y_sum / n) / (xx_sum - x_sum * x_sum / n)
+            })
+            .collect())
+    }
+
+    // VaR & CVaR are reported as positive loss fractions of one period
+    // confidence: 0.95 means the worst 5% periods
+    #[pyo3(signature = (confidence=0.95))]
+    pub fn var_historical(&self, confidence: f64) -> Result<f64> {
+        check_confidence(confidence)?;
+        let mut returns = self.period_returns();
+        if returns.is_empty() {
+            return Ok(f64::NAN);
+        }
+        returns.sort_by(|a, b| a.total_cmp(b));
+        Ok(-quantile_of(&returns, 1.0 - confidence))
+    }
+
+    #[pyo3(signature = (confidence=0.95))]
+    pub fn cvar_historical(&self, confidence: f64) -> Result<f64> {
+        check_confidence(confidence)?;
+        let mut returns = self.period_returns();
+        if returns.is_empty() {
+            return Ok(f64::NAN);
+        }
+        returns.sort_by(|a, b| a.total_cmp(b));
+        let cutoff = quantile_of(&returns, 1.0 - confidence);
+        let tail: Vec<f64> = returns.into_iter().filter(|&r| r <= cutoff).collect();
+        Ok(-tail.iter().sum::<f64>() / tail.len() as f64)
+    }
+
+    // gaussian VaR, or Cornish-Fisher VaR adjusted by skewness & excess kurtosis
+    #[pyo3(signature = (confidence=0.95, cornish_fisher=false))]
+    pub fn var_parametric(&self, confidence: f64, cornish_fisher: bool) -> Result<f64> {
+        check_confidence(confidence)?;
+        let (mean, stdev, skew, kurt) = moments_of(&self.period_returns());
+        let z = norm_ppf(1.0 - confidence);
+        let z = if cornish_fisher { cornish_fisher_z(z, skew, kurt) } else { z };
+        Ok(-(mean + z * stdev))
+    }
+
+    #[pyo3(signature = (confidence=0.95, cornish_fisher=false))]
+    pub fn cvar_parametric(&self, confidence: f64, cornish_fisher: bool) -> Result<f64> {
+        check_confidence(confidence)?;
+        let (mean, stdev, skew, kurt) = moments_of(&self.period_returns());
+        let alpha = 1.0 - confidence;
+        if cornish_fisher {
+            // average the Cornish-Fisher quantiles over the tail
+            let steps = 100;
+            let tail_mean = (0..steps).map(|i| cornish_fisher_z(norm_ppf(alpha * (i as f64 + 0.5) / steps as f64), skew, kurt)).sum::<f64>() / steps as f64;
+            Ok(-(mean + tail_mean * stdev))
+        } else {
+            let z = norm_ppf(alpha);
+            Ok(-(mean - stdev * norm_pdf(z) / alpha))
+        }
+    }
+
+    // root mean square of drawdowns from running max
+    pub fn ulcer_index(&self) -> f64 {
+        if self.equity_curve.is_empty() {
+            return f64::NAN;
+        }
+        let mut running_max = f64::NEG_INFINITY;
+        let mut sq_sum = 0.0;
+        for &portfolio_value in self.equity_curve.iter() {
+            running_max = running_max.max(portfolio_value);
+            sq_sum += ((running_max - portfolio_value) / running_max).powi(2);
+        }
+        (sq_sum / self.equity_curve.len() as f64).sqrt()
+    }
+
+    // UPI, Martin ratio
+    pub fn ulcer_performance_index(&self, risk_free_rate: f64) -> f64 {
+        (self.cagr() - risk_free_rate) / self.ulcer_index()
+    }
+
+    pub fn calmar_ratio(&self) -> f64 {
+        self.cagr() / self.max_drawdown()
+    }
+
+    // average yearly max drawdown plus excess (usually 10%) as denominator
+    #[pyo3(signature = (excess=0.1))]
+    pub fn sterling_ratio(&self, excess: f64) -> f64 {
+        let year_len = (self.periods_per_year.round() as usize).max(1);
+        let drawdowns: Vec<f64> = self.equity_curve.chunks(year_len).map(max_drawdown_of).collect();
+        if drawdowns.is_empty() {
+            return f64::NAN;
+        }
+        let avg_drawdown = drawdowns.iter().sum::<f64>() / drawdowns.len() as f64;
+        self.cagr() / (avg_drawdown + excess)
+    }
+
+    // threshold: annual minimum acceptable return
+    #[pyo3(signature = (threshold=0.0))]
+    pub fn omega_ratio(&self, threshold: f64) -> f64 {
+        let period_threshold = (1.0 + threshold).powf(1.0 / self.periods_per_year) - 1.0;
+        let (gains, losses) = self.period_returns().iter().fold((0.0, 0.0), |(gains, losses), &r| {
+            let excess = r - period_threshold;
+            if excess > 0.0 {
+                (gains + excess, losses)
+            } else {
+                (gains, losses - excess)
+            }
+        });
+        gains / losses
+    }
+
+    // right tail over left tail, 95th percentile / 5th percentile
+    pub fn tail_ratio(&self) -> f64 {
+        let mut returns = self.period_returns();
+        if returns.is_empty() {
+            return f64::NAN;
+        }
+        returns.sort_by(|a, b| a.total_cmp(b));
+        (quantile_of(&returns, 0.95) / quantile_of(&returns, 0.05)).abs()
+    }
+}
+
+impl Analyzer {
+    // simple returns aligned with equity_curve, the first one is NAN
+    fn returns(&self) -> Vec<f64> {
+        returns_of(&self.equity_curve)
+    }
+
+    // simple returns without the leading NAN
+    fn period_returns(&self) -> Vec<f64> {
+        self.returns().into_iter().filter(|r| r.is_finite()).collect()
+    }
+}
+
+fn returns_of(values: &[f64]) -> Vec<f64> {
+    let mut returns = Vec::with_capacity(values.len());
+    if !values.is_empty() {
+        returns.push(f64::NAN);
+    }
+    for i in 1..values.len() {
+        returns.push(values[i] / values[i - 1] - 1.0);
+    }
+    returns
+}
+
+// a confidence of 0 or 1 has no tail to measure
+fn check_confidence(confidence: f64) -> Result<()> {
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err(Error::InvalidParameter(format!("confidence must be in (0, 1), got {}", confidence)));
+    }
+    Ok(())
+}
+
+// rolling metrics need two returns for a sample variance
+fn check_window(window: usize) -> Result<()> {
+    if window < 2 {
+        return Err(Error::InvalidParameter(format!("rolling window must be at least 2, got {}", window)));
+    }
+    Ok(())
+}
+
+fn max_drawdown_of(values: &[f64]) -> f64 {
+    let mut running_max = f64::NEG_INFINITY;
+    let mut max_drawdown = 0.0;
+
+    for &portfolio_value in values.iter() {
+        if portfolio_value > running_max {
+            running_max = portfolio_value;
+        } else {
+            let drawdown = (running_max - portfolio_value) / running_max;
+            if drawdown > max_drawdown {
+                max_drawdown = drawdown;
+            }
+        }
+    }
+
+    max_drawdown
+}
+
+// linear interpolated quantile of sorted values, same as CumQuantile
+fn quantile_of(sorted: &[f64], quantile: f64) -> f64 {
+    let index = (sorted.len() - 1) as f64 * quantile;
+    let lower_index = index.floor() as usize;
+    let fraction = index - lower_index as f64;
+
+    let lower_value = sorted[lower_index];
+    let upper_value = if lower_index + 1 < sorted.len() { sorted[lower_index + 1] } else { lower_value };
+
+    lower_value + fraction * (upper_value - lower_value)
+}
+
+// (mean, sample stdev, skewness, excess kurtosis)
+fn moments_of(values: &[f64]) -> (f64, f64, f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let m2 = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    let m3 = values.iter().map(|v| (v - mean).powi(3)).sum::<f64>() / n;
+    let m4 = values.iter().map(|v| (v - mean).powi(4)).sum::<f64>() / n;
+    let stdev = (m2 * n / (n - 1.0)).sqrt();
+    (mean, stdev, m3 / m2.powf(1.5), m4 / (m2 * m2) - 3.0)
+}
+
+fn cornish_fisher_z(z: f64, skew: f64, kurt: f64) -> f64 {
+    z + (z * z - 1.0) * skew / 6.0 + (z.powi(3) - 3.0 * z) * kurt / 24.0 - (2.0 * z.powi(3) - 5.0 * z) * skew * skew / 36.0
+}
+
+fn norm_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+// inverse of standard normal cdf, Acklam's rational approximation
+fn norm_ppf(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e+01, 2.209460984245205e+02, -2.759285104469687e+02, 1.38357751867269e+02, -3.066479806614716e+01, 2.506628277459239e+00];
+    const B: [f64; 5] = [-5.447609879822406e+01, 1.615858368580409e+02, -1.556989798598866e+02, 6.680131188771972e+01, -1.328068155288572e+01];
+    const C: [f64; 6] = [-7.784894002430293e-03, -3.223964580411365e-01, -2.400758277161838e+00, -2.549732539343734e+00, 4.374664141464968e+00, 2.938163982698783e+00];
+    const D: [f64; 4] = [7.784695709041462e-03, 3.224671290700398e-01, 2.445134137142996e+00, 3.754408661907416e+00];
+    let p_low = 0.02425;
+
+    if p <= 0.0 || p >= 1.0 {
+        f64::NAN
+    } else if p < p_low {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - p_low {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -(((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    }
+}
+
+impl_snapshot!(Analyzer);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analyzer_of(values: &[f64]) -> Analyzer {
+        let mut analyzer = Analyzer::new(CN_TRADING_DAYS);
+        for (i, &value) in values.iter().enumerate() {
+            analyzer.update(i as i64, value);
+        }
+        analyzer
+    }
+
+    #[test]
+    fn rolling_sortino_skips_nan_returns() {
+        let analyzer = analyzer_of(&[100.0, 101.0, 102.0, f64::NAN, 100.0, 99.0, 101.0]);
+        let sortino = analyzer.rolling_sortino(2, 0.0, 0.0).unwrap();
+        // every window holding one of the NAN returns at 0, 3 and 4 is NAN
+        for i in [0, 1, 3, 4, 5] {
+            assert!(sortino[i].is_nan(), "sortino[{}] = {}", i, sortino[i]);
+        }
+        assert!(sortino[2].is_finite());
+        // the last window only holds the returns of 100 -> 99 -> 101
+        let expected = analyzer_of(&[100.0, 99.0, 101.0]).rolling_sortino(2, 0.0, 0.0).unwrap()[2];
+        assert!(expected.is_finite());
+        assert!((sortino[6] - expected).abs() < 1e-12);
+    }
 }
@@ -1,10 +1,13 @@
-use super::analyzer::Analyzer;
-use crate::datatype::{position::Position, position::PositionStatus, quote::Bar};
+use super::analyzer::{Analyzer, CN_TRADING_DAYS};
+use crate::datatype::{fill::Fill, fill::Side, position::Position, position::PositionStatus, quote::Bar};
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[pyclass]
-#[derive(Clone)] // for the #[pyo3(get)] in strategies
+#[pyclass(module = "bktrader.broker")]
+#[derive(Clone, Serialize, Deserialize)] // for the #[pyo3(get)] in strategies
 pub struct EtfBroker {
     pub init_cash: f64,
     #[pyo3(get)]
@@ -16,10 +19,23 @@ pub struct EtfBroker {
     #[pyo3(get)]
     pub positions: Vec<Position>,
     #[pyo3(get)]
-    total_fees: f64,
+    pub fills: Vec<Fill>,
     #[pyo3(get)]
-    analyzer: Analyzer,
+    pub total_fees: f64,
+    #[pyo3(get)]
+    pub analyzer: Analyzer,
     pos_id: u32,
+    // traded amount in current bar, reset after update_portfolio_value
+    bar_traded_amount: f64,
+}
+
+// a NaN price (e.g. vwap of a bar without volume) would silently turn cash into NaN
+fn check_price(price: f64) -> Result<()> {
+    if price.is_finite() && price > 0.0 {
+        Ok(())
+    } else {
+        Err(Error::InvalidOrder(format!("price must be positive, got {}", price)))
+    }
 }
 
 impl EtfBroker {
@@ -33,8 +49,8 @@ impl EtfBroker {
 #[pymethods]
 impl EtfBroker {
     #[new]
-    #[pyo3(signature = (init_cash=5e4, ftc=5.0, ptc=1.5e-4))]
-    pub fn new(init_cash: f64, ftc: f64, ptc: f64) -> Self {
+    #[pyo3(signature = (init_cash=5e4, ftc=5.0, ptc=1.5e-4, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(init_cash: f64, ftc: f64, ptc: f64, periods_per_year: f64) -> Self {
         Self {
             init_cash,
             cash: init_cash,
@@ -44,17 +60,24 @@ impl EtfBroker {
             //  proportional transaction costs per trade (buy or sell)
             ptc,
             positions: Vec::with_capacity(100),
+            fills: Vec::with_capacity(200),
             total_fees: 0.0,
-            analyzer: Analyzer::new(),
+            analyzer: Analyzer::new(periods_per_year),
             pos_id: 0,
+            bar_traded_amount: 0.0,
         }
     }
 
     #[pyo3(signature = (bar, price, volume, stop_loss=None, take_profit=None))]
-    pub fn entry(&mut self, bar: &Bar, price: f64, volume: f64, stop_loss: Option<f64>, take_profit: Option<f64>) -> u32 {
+    pub fn entry(&mut self, bar: &Bar, price: f64, volume: f64, stop_loss: Option<f64>, take_profit: Option<f64>) -> Result<u32> {
+        check_price(price)?;
+        if !(volume.is_finite() && volume >= 0.0) {
+            return Err(Error::InvalidOrder(format!("entry volume must not be negative, got {}", volume)));
+        }
         let deal_amount = price * volume;
         let fees = self.charge(deal_amount);
         self.cash -= deal_amount + fees;
+        self.bar_traded_amount += deal_amount;
 
         // open position
         self.pos_id += 1;
@@ -65,32 +88,46 @@ impl EtfBroker {
         // println!("entry {:?}", pos);
 
         self.positions.push(pos);
+        self.fills.push(Fill::new(self.fills.len() as u32 + 1, bar.dt, Side::Buy, price, volume, fees, vec![pos.id]));
 
         // return position id
-        pos.id
+        Ok(pos.id)
     }
 
-    pub fn exit(&mut self, bar: &Bar, position_ids: Vec<u32>, price: f64) {
-        // position_id: index mapping in all positions
-        let position_map: HashMap<u32, usize> = self.positions.iter().enumerate().map(|(i, pos)| (pos.id, i)).collect();
+    pub fn exit(&mut self, bar: &Bar, position_ids: Vec<u32>, price: f64) -> Result<()> {
+        check_price(price)?;
+        // position_id: index mapping of opened positions, an id is removed once it is sold
+        let mut position_map: HashMap<u32, usize> =
+            self.positions.iter().enumerate().filter(|(_, pos)| pos.status == PositionStatus::Opened).map(|(i, pos)| (pos.id, i)).collect();
 
-        let mut sold_vol = 0.0;
+        // validate every id before closing any position
+        let mut sold_ids = Vec::with_capacity(position_ids.len());
         let mut indices_to_update = Vec::with_capacity(position_ids.len());
         for id in position_ids {
-            if let Some(&index) = position_map.get(&id) {
-                let position = &mut self.positions[index];
-                position.status = PositionStatus::Closed;
-                position.exit_dt = Some(bar.dt);
-                position.exit_price = Some(price);
-                sold_vol += position.volume;
-                indices_to_update.push(index);
-            }
+            let index = position_map.remove(&id).ok_or_else(|| Error::InvalidOrder(format!("position {} is not opened", id)))?;
+            sold_ids.push(id);
+            indices_to_update.push(index);
+        }
+
+        let mut sold_vol = 0.0;
+        for &index in &indices_to_update {
+            let position = &mut self.positions[index];
+            // the exit bar still counts, update_portfolio_value() only sees opened positions
+            position.update_excursion(bar.dt, bar.high.max(price), bar.low.min(price));
+            position.status = PositionStatus::Closed;
+            position.exit_dt = Some(bar.dt);
+            position.exit_price = Some(price);
+            sold_vol += position.volume;
         }
 
         // Calculate deal amount and fees
         let deal_amount = price * sold_vol;
         let fees = self.charge(deal_amount);
         self.cash += deal_amount - fees;
+        self.bar_traded_amount += deal_amount;
+        if !sold_ids.is_empty() {
+            self.fills.push(Fill::new(self.fills.len() as u32 + 1, bar.dt, Side::Sell, price, sold_vol, fees, sold_ids));
+        }
 
         // Calculate average fees
         let avg_fees = if !indices_to_update.is_empty() { fees / indices_to_update.len() as f64 } else { 0.0 };
@@ -103,11 +140,15 @@ impl EtfBroker {
             position.pnl = (price - position.entry_price) * position.volume;
             // println!("exit {:?}", position);
         }
+        Ok(())
     }
 
     pub fn update_portfolio_value(&mut self, bar: &Bar) {
-        self.portfolio_value = self.cash + self.active_positions_sum() * bar.close;
-        self.analyzer.update(self.portfolio_value);
+        let invested_value = self.active_positions_sum() * bar.close;
+        self.portfolio_value = self.cash + invested_value;
+        self.analyzer.update(bar.dt as i64, self.portfolio_value);
+        self.analyzer.update_exposure(self.cash, invested_value, 0.0, self.active_position_len(), self.bar_traded_amount);
+        self.bar_traded_amount = 0.0;
         self.update_active_pnl(bar);
         self.positions.iter_mut().for_each(|pos| {
             if pos.entry_dt != bar.dt && pos.exit_dt.is_none() {
@@ -123,6 +164,7 @@ impl EtfBroker {
     pub fn update_active_pnl(&mut self, bar: &Bar) {
         self.positions.iter_mut().filter(|pos| pos.status == PositionStatus::Opened).for_each(|pos| {
             pos.pnl = (bar.close - pos.entry_price) * pos.volume;
+            pos.update_excursion(bar.dt, bar.high, bar.low);
         });
     }
 
@@ -207,3 +249,5 @@ impl EtfBroker {
         self.total_fees / self.init_cash
     }
 }
+
+impl_snapshot!(EtfBroker);
@@ -1,10 +1,16 @@
 use pyo3::prelude::*;
 pub mod analyzer;
 pub mod etf;
+pub mod report;
+pub mod store;
 
 pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
     let broker = PyModule::new(parent_module.py(), "broker")?;
     broker.add_class::<etf::EtfBroker>()?;
     broker.add_class::<analyzer::Analyzer>()?;
+    broker.add_class::<report::BacktestReport>()?;
+    broker.add_class::<store::ResultStore>()?;
+    // register as bktrader.broker so that pickle can import the classes
+    parent_module.py().import("sys")?.getattr("modules")?.set_item("bktrader.broker", &broker)?;
     parent_module.add_submodule(&broker)
 }
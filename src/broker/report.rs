@@ -0,0 +1,287 @@
+use super::etf::EtfBroker;
+use crate::datatype::{fill::Fill, position::Position};
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
+use duckdb::arrow::array::{Array, ArrayRef, Float64Array, Int64Array, RecordBatch, UInt64Array};
+use duckdb::arrow::datatypes::{DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::file::metadata::KeyValue;
+use parquet::file::properties::WriterProperties;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::sync::Arc;
+
+// parquet key-value metadata holding everything except the per-bar columns
+const REPORT_META_KEY: &str = "bktrader.report";
+
+#[pyclass(module = "bktrader.broker")]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct BacktestReport {
+    #[pyo3(get)]
+    pub name: String,
+    #[pyo3(get)]
+    pub params: BTreeMap<String, String>,
+    // undefined ratios are NaN, which json writes as null
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_map_as_null")]
+    pub metrics: BTreeMap<String, f64>,
+    // per-bar series, aligned with dts
+    #[pyo3(get)]
+    pub dts: Vec<i64>,
+    #[pyo3(get)]
+    pub equity_curve: Vec<f64>,
+    #[pyo3(get)]
+    pub cash_curve: Vec<f64>,
+    #[pyo3(get)]
+    pub invested_curve: Vec<f64>,
+    // NaN while the portfolio value is zero
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
+    pub gross_exposure: Vec<f64>,
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
+    pub net_exposure: Vec<f64>,
+    #[pyo3(get)]
+    pub active_counts: Vec<usize>,
+    #[pyo3(get)]
+    pub turnover_curve: Vec<f64>,
+    #[pyo3(get)]
+    pub positions: Vec<Position>,
+    #[pyo3(get)]
+    pub fills: Vec<Fill>,
+}
+
+impl BacktestReport {
+    pub fn from_broker(broker: &EtfBroker, name: &str, params: BTreeMap<String, String>, risk_free_rate: f64, mar: f64) -> Result<Self> {
+        let analyzer = &broker.analyzer;
+        let (sharpe_annual, sharpe_volatility, sharpe_ratio) = analyzer.sharpe_ratio(risk_free_rate);
+        let (sortino_annual, sortino_volatility, sortino_ratio) = analyzer.sortino_ratio(risk_free_rate, mar);
+        let metrics = BTreeMap::from([
+            ("profit_net".to_string(), broker.profit_net()),
+            ("total_fees".to_string(), broker.total_fees),
+            ("avg_hold_days".to_string(), broker.avg_hold_days()),
+            ("total_return".to_string(), analyzer.total_return()),
+            ("cagr".to_string(), analyzer.cagr()),
+            ("max_drawdown".to_string(), analyzer.max_drawdown()),
+            ("max_drawup".to_string(), analyzer.max_drawup()),
+            ("sharpe_annual".to_string(), sharpe_annual),
+            ("sharpe_volatility".to_string(), sharpe_volatility),
+            ("sharpe_ratio".to_string(), sharpe_ratio),
+            ("sortino_annual".to_string(), sortino_annual),
+            ("sortino_volatility".to_string(), sortino_volatility),
+            ("sortino_ratio".to_string(), sortino_ratio),
+            ("calmar_ratio".to_string(), analyzer.calmar_ratio()),
+            ("ulcer_index".to_string(), analyzer.ulcer_index()),
+            ("var_95".to_string(), analyzer.var_historical(0.95)?),
+            ("cvar_95".to_string(), analyzer.cvar_historical(0.95)?),
+            ("avg_exposure".to_string(), analyzer.avg_exposure()),
+            ("time_in_market".to_string(), analyzer.time_in_market()),
+            ("annual_turnover".to_string(), analyzer.annual_turnover()),
+        ]);
+
+        Ok(Self {
+            name: name.to_string(),
+            params,
+            metrics,
+            dts: analyzer.dts.clone(),
+            equity_curve: analyzer.equity_curve.clone(),
+            cash_curve: analyzer.cash_curve.clone(),
+            invested_curve: analyzer.invested_curve.clone(),
+            gross_exposure: analyzer.gross_exposure.clone(),
+            net_exposure: analyzer.net_exposure.clone(),
+            active_counts: analyzer.active_counts.clone(),
+            turnover_curve: analyzer.turnover_curve.clone(),
+            positions: broker.positions.clone(),
+            fills: broker.fills.clone(),
+        })
+    }
+
+    fn record_batch(&self) -> Result<RecordBatch> {
+        let float_field = |name: &str| Field::new(name, DataType::Float64, true);
+        let schema = Schema::new(vec![
+            Field::new("dt", DataType::Int64, false),
+            float_field("equity"),
+            float_field("cash"),
+            float_field("invested"),
+            float_field("gross_exposure"),
+            float_field("net_exposure"),
+            Field::new("active_count", DataType::UInt64, false),
+            float_field("turnover"),
+        ]);
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(self.dts.clone())),
+            Arc::new(Float64Array::from(self.equity_curve.clone())),
+            Arc::new(Float64Array::from(self.cash_curve.clone())),
+            Arc::new(Float64Array::from(self.invested_curve.clone())),
+            Arc::new(Float64Array::from(self.gross_exposure.clone())),
+            Arc::new(Float64Array::from(self.net_exposure.clone())),
+            Arc::new(UInt64Array::from(self.active_counts.iter().map(|&count| count as u64).collect::<Vec<u64>>())),
+            Arc::new(Float64Array::from(self.turnover_curve.clone())),
+        ];
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+
+    fn append_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let floats = |name: &str| -> Result<Vec<f64>> {
+            let column = batch.column_by_name(name).ok_or_else(|| Error::Data(format!("missing column {}", name)))?;
+            let array = column.as_any().downcast_ref::<Float64Array>().ok_or_else(|| Error::Data(format!("column {} is not f64", name)))?;
+            Ok(array.iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+        };
+        let column = |name: &str| batch.column_by_name(name).ok_or_else(|| Error::Data(format!("missing column {}", name)));
+        let dts = column("dt")?.as_any().downcast_ref::<Int64Array>().ok_or_else(|| Error::Data("column dt is not i64".into()))?;
+        let active_counts = column("active_count")?.as_any().downcast_ref::<UInt64Array>().ok_or_else(|| Error::Data("column active_count is not u64".into()))?;
+
+        self.dts.extend(dts.values().iter());
+        self.equity_curve.extend(floats("equity")?);
+        self.cash_curve.extend(floats("cash")?);
+        self.invested_curve.extend(floats("invested")?);
+        self.gross_exposure.extend(floats("gross_exposure")?);
+        self.net_exposure.extend(floats("net_exposure")?);
+        self.active_counts.extend(active_counts.values().iter().map(|&count| count as usize));
+        self.turnover_curve.extend(floats("turnover")?);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl BacktestReport {
+    #[new]
+    #[pyo3(signature = (broker, name="", params=None, risk_free_rate=0.015, mar=0.01))]
+    pub fn new(broker: &EtfBroker, name: &str, params: Option<&Bound<'_, PyDict>>, risk_free_rate: f64, mar: f64) -> PyResult<Self> {
+        let mut param_map = BTreeMap::new();
+        if let Some(params) = params {
+            for (key, value) in params.iter() {
+                param_map.insert(key.str()?.to_string(), value.str()?.to_string());
+            }
+        }
+        Ok(Self::from_broker(broker, name, param_map, risk_free_rate, mar)?)
+    }
+
+    pub fn save_json(&self, path: &str) -> Result<()> {
+        let file = File::create(path)?;
+        Ok(serde_json::to_writer(file, self)?)
+    }
+
+    #[staticmethod]
+    pub fn load_json(path: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    // per-bar series as parquet columns, the rest as json in the file metadata
+    pub fn save_parquet(&self, path: &str) -> Result<()> {
+        let batch = self.record_batch()?;
+        let meta = Self {
+            dts: Vec::new(),
+            equity_curve: Vec::new(),
+            cash_curve: Vec::new(),
+            invested_curve: Vec::new(),
+            gross_exposure: Vec::new(),
+            net_exposure: Vec::new(),
+            active_counts: Vec::new(),
+            turnover_curve: Vec::new(),
+            ..self.clone()
+        };
+        let props = WriterProperties::builder()
+            .set_key_value_metadata(Some(vec![KeyValue::new(REPORT_META_KEY.to_string(), crate::snapshot::to_json(&meta)?)]))
+            .build();
+
+        let file = File::create(path)?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    #[staticmethod]
+    pub fn load_parquet(path: &str) -> Result<Self> {
+        let file = File::open(path)?;
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)?;
+        let meta_json = builder
+            .metadata()
+            .file_metadata()
+            .key_value_metadata()
+            .and_then(|kvs| kvs.iter().find(|kv| kv.key == REPORT_META_KEY))
+            .and_then(|kv| kv.value.clone())
+            .ok_or_else(|| Error::Data(format!("{} is not a backtest report", path)))?;
+        let mut report: Self = crate::snapshot::from_json(&meta_json)?;
+
+        let reader = builder.build()?;
+        for batch in reader {
+            let batch = batch?;
+            report.append_batch(&batch)?;
+        }
+        Ok(report)
+    }
+}
+
+impl_snapshot!(BacktestReport);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::broker::analyzer::Analyzer;
+
+    // the second bar has a zero portfolio value, so both exposures are NaN there
+    fn report_with_zero_portfolio_bar() -> BacktestReport {
+        let mut analyzer = Analyzer::new(243.0);
+        for (dt, value, invested) in [(1, 100.0, 50.0), (2, 0.0, 0.0), (3, 120.0, 60.0)] {
+            analyzer.update(dt, value);
+            analyzer.update_exposure(value - invested, invested, 0.0, 1, 0.0);
+        }
+        BacktestReport {
+            name: "zero".to_string(),
+            dts: analyzer.dts,
+            equity_curve: analyzer.equity_curve,
+            cash_curve: analyzer.cash_curve,
+            invested_curve: analyzer.invested_curve,
+            gross_exposure: analyzer.gross_exposure,
+            net_exposure: analyzer.net_exposure,
+            active_counts: analyzer.active_counts,
+            turnover_curve: analyzer.turnover_curve,
+            ..Default::default()
+        }
+    }
+
+    fn assert_exposures(report: &BacktestReport) {
+        for exposure in [&report.gross_exposure, &report.net_exposure] {
+            assert_eq!(exposure.len(), 3);
+            assert_eq!(exposure[0], 0.5);
+            assert!(exposure[1].is_nan());
+            assert_eq!(exposure[2], 0.5);
+        }
+    }
+
+    #[test]
+    fn json_round_trip_with_zero_portfolio_bar() {
+        let report = report_with_zero_portfolio_bar();
+        assert_exposures(&report);
+        let path = std::env::temp_dir().join(format!("bktrader-report-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        report.save_json(path).unwrap();
+        let loaded = BacktestReport::load_json(path);
+        std::fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.dts, report.dts);
+        assert_eq!(loaded.equity_curve, report.equity_curve);
+        assert_exposures(&loaded);
+    }
+
+    #[test]
+    fn parquet_round_trip_with_zero_portfolio_bar() {
+        let report = report_with_zero_portfolio_bar();
+        let path = std::env::temp_dir().join(format!("bktrader-report-{}.parquet", std::process::id()));
+        let path = path.to_str().unwrap();
+        report.save_parquet(path).unwrap();
+        let loaded = BacktestReport::load_parquet(path);
+        std::fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.dts, report.dts);
+        assert_eq!(loaded.equity_curve, report.equity_curve);
+        assert_exposures(&loaded);
+    }
+}
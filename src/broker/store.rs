@@ -0,0 +1,296 @@
+use super::report::BacktestReport;
+use crate::datatype::{
+    fill::{Fill, Side},
+    position::{Position, PositionStatus},
+};
+use crate::error::{Error, Result};
+use duckdb::{params, Connection};
+use pyo3::prelude::*;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// every table is keyed by run_id, so runs can be joined and compared with plain SQL
+const SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS bt_runs (
+        run_id BIGINT PRIMARY KEY,
+        name VARCHAR,
+        created_at TIMESTAMP, -- utc
+        start_dt BIGINT,
+        end_dt BIGINT,
+        bars BIGINT
+    );
+    CREATE TABLE IF NOT EXISTS bt_params (run_id BIGINT, key VARCHAR, value VARCHAR);
+    CREATE TABLE IF NOT EXISTS bt_metrics (run_id BIGINT, key VARCHAR, value DOUBLE);
+    CREATE TABLE IF NOT EXISTS bt_equity (
+        run_id BIGINT,
+        dt BIGINT,
+        equity DOUBLE,
+        cash DOUBLE,
+        invested DOUBLE,
+        gross_exposure DOUBLE,
+        net_exposure DOUBLE,
+        active_count UBIGINT,
+        turnover DOUBLE
+    );
+    CREATE TABLE IF NOT EXISTS bt_positions (
+        run_id BIGINT,
+        id UINTEGER,
+        entry_dt INTEGER,
+        exit_dt INTEGER,
+        entry_price DOUBLE,
+        exit_price DOUBLE,
+        stop_loss DOUBLE,
+        take_profit DOUBLE,
+        status VARCHAR,
+        volume DOUBLE,
+        pnl DOUBLE,
+        fees DOUBLE,
+        holding_days UINTEGER,
+        mae_price DOUBLE,
+        mae_pct DOUBLE,
+        mae_dt INTEGER,
+        mfe_price DOUBLE,
+        mfe_pct DOUBLE,
+        mfe_dt INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS bt_fills (
+        run_id BIGINT,
+        id UINTEGER,
+        dt INTEGER,
+        side VARCHAR,
+        price DOUBLE,
+        volume DOUBLE,
+        fees DOUBLE
+    );
+    -- an exit fill may close several positions
+    CREATE TABLE IF NOT EXISTS bt_fill_positions (run_id BIGINT, fill_id UINTEGER, position_id UINTEGER);
+"#;
+
+const RESULT_TABLES: [&str; 7] = ["bt_runs", "bt_params", "bt_metrics", "bt_equity", "bt_positions", "bt_fills", "bt_fill_positions"];
+
+#[pyclass(module = "bktrader.broker")]
+pub struct ResultStore {
+    #[pyo3(get)]
+    uri: String,
+}
+
+impl ResultStore {
+    fn connect(&self) -> Result<Connection> {
+        let conn = Connection::open(&self.uri)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(conn)
+    }
+
+    fn write(conn: &mut Connection, report: &BacktestReport) -> Result<i64> {
+        let tx = conn.transaction()?;
+        let run_id: i64 = tx.query_row("SELECT COALESCE(MAX(run_id), 0) + 1 FROM bt_runs", [], |row| row.get(0))?;
+        // utc micros, current_localtimestamp() would require the icu extension
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_micros() as i64);
+        tx.execute(
+            "INSERT INTO bt_runs VALUES (?, ?, make_timestamp(?), ?, ?, ?)",
+            params![run_id, report.name, created_at, report.dts.first(), report.dts.last(), report.dts.len() as i64],
+        )?;
+
+        {
+            let mut appender = tx.appender("bt_params")?;
+            for (key, value) in &report.params {
+                appender.append_row(params![run_id, key, value])?;
+            }
+            appender.flush()?;
+
+            let mut appender = tx.appender("bt_metrics")?;
+            for (key, value) in &report.metrics {
+                appender.append_row(params![run_id, key, value])?;
+            }
+            appender.flush()?;
+
+            let mut appender = tx.appender("bt_equity")?;
+            for i in 0..report.dts.len() {
+                appender.append_row(params![
+                    run_id,
+                    report.dts[i],
+                    report.equity_curve[i],
+                    report.cash_curve[i],
+                    report.invested_curve[i],
+                    report.gross_exposure[i],
+                    report.net_exposure[i],
+                    report.active_counts[i] as u64,
+                    report.turnover_curve[i],
+                ])?;
+            }
+            appender.flush()?;
+
+            let mut appender = tx.appender("bt_positions")?;
+            for pos in &report.positions {
+                appender.append_row(params![
+                    run_id,
+                    pos.id,
+                    pos.entry_dt,
+                    pos.exit_dt,
+                    pos.entry_price,
+                    pos.exit_price,
+                    pos.stop_loss,
+                    pos.take_profit,
+                    format!("{:?}", pos.status),
+                    pos.volume,
+                    pos.pnl,
+                    pos.fees,
+                    pos.holding_days,
+                    pos.mae_price,
+                    pos.mae_pct,
+                    pos.mae_dt,
+                    pos.mfe_price,
+                    pos.mfe_pct,
+                    pos.mfe_dt,
+                ])?;
+            }
+            appender.flush()?;
+
+            let mut appender = tx.appender("bt_fills")?;
+            for fill in &report.fills {
+                appender.append_row(params![run_id, fill.id, fill.dt, format!("{:?}", fill.side), fill.price, fill.volume, fill.fees])?;
+            }
+            appender.flush()?;
+
+            let mut appender = tx.appender("bt_fill_positions")?;
+            for fill in &report.fills {
+                for position_id in &fill.position_ids {
+                    appender.append_row(params![run_id, fill.id, position_id])?;
+                }
+            }
+            appender.flush()?;
+        }
+
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    fn read(conn: &Connection, run_id: i64) -> Result<Option<BacktestReport>> {
+        let mut stmt = conn.prepare("SELECT name FROM bt_runs WHERE run_id = ?")?;
+        let mut rows = stmt.query(params![run_id])?;
+        let name: String = match rows.next()? {
+            Some(row) => row.get(0)?,
+            None => return Ok(None),
+        };
+        let mut report = BacktestReport {
+            name,
+            ..Default::default()
+        };
+
+        let mut stmt = conn.prepare("SELECT key, value FROM bt_params WHERE run_id = ?")?;
+        report.params = stmt.query_map(params![run_id], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<std::result::Result<BTreeMap<_, _>, _>>()?;
+        let mut stmt = conn.prepare("SELECT key, value FROM bt_metrics WHERE run_id = ?")?;
+        report.metrics = stmt
+            .query_map(params![run_id], |row| Ok((row.get(0)?, row.get::<_, Option<f64>>(1)?.unwrap_or(f64::NAN))))?
+            .collect::<std::result::Result<BTreeMap<_, _>, _>>()?;
+
+        let mut stmt = conn.prepare("SELECT dt, equity, cash, invested, gross_exposure, net_exposure, active_count, turnover FROM bt_equity WHERE run_id = ? ORDER BY dt")?;
+        let mut rows = stmt.query(params![run_id])?;
+        while let Some(row) = rows.next()? {
+            report.dts.push(row.get(0)?);
+            report.equity_curve.push(row.get(1)?);
+            report.cash_curve.push(row.get(2)?);
+            report.invested_curve.push(row.get(3)?);
+            report.gross_exposure.push(row.get(4)?);
+            report.net_exposure.push(row.get(5)?);
+            report.active_counts.push(row.get(6)?);
+            report.turnover_curve.push(row.get(7)?);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT id, entry_dt, exit_dt, entry_price, exit_price, stop_loss, take_profit, status, volume, pnl, fees, holding_days,
+                mae_price, mae_pct, mae_dt, mfe_price, mfe_pct, mfe_dt
+            FROM bt_positions WHERE run_id = ? ORDER BY id",
+        )?;
+        report.positions = stmt
+            .query_map(params![run_id], |row| {
+                let status: String = row.get(7)?;
+                Ok(Position {
+                    id: row.get(0)?,
+                    entry_dt: row.get(1)?,
+                    exit_dt: row.get(2)?,
+                    entry_price: row.get(3)?,
+                    exit_price: row.get(4)?,
+                    stop_loss: row.get(5)?,
+                    take_profit: row.get(6)?,
+                    status: if status == "Closed" { PositionStatus::Closed } else { PositionStatus::Opened },
+                    volume: row.get(8)?,
+                    pnl: row.get(9)?,
+                    fees: row.get(10)?,
+                    holding_days: row.get(11)?,
+                    mae_price: row.get(12)?,
+                    mae_pct: row.get(13)?,
+                    mae_dt: row.get(14)?,
+                    mfe_price: row.get(15)?,
+                    mfe_pct: row.get(16)?,
+                    mfe_dt: row.get(17)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        // an exit fill may close several positions
+        let mut position_ids: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+        let mut stmt = conn.prepare("SELECT fill_id, position_id FROM bt_fill_positions WHERE run_id = ? ORDER BY fill_id, position_id")?;
+        let mut rows = stmt.query(params![run_id])?;
+        while let Some(row) = rows.next()? {
+            position_ids.entry(row.get(0)?).or_default().push(row.get(1)?);
+        }
+
+        let mut stmt = conn.prepare("SELECT id, dt, side, price, volume, fees FROM bt_fills WHERE run_id = ? ORDER BY id")?;
+        report.fills = stmt
+            .query_map(params![run_id], |row| {
+                let id: u32 = row.get(0)?;
+                let side: String = row.get(2)?;
+                Ok(Fill::new(
+                    id,
+                    row.get(1)?,
+                    if side == "Sell" { Side::Sell } else { Side::Buy },
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                    position_ids.remove(&id).unwrap_or_default(),
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(Some(report))
+    }
+}
+
+#[pymethods]
+impl ResultStore {
+    #[new]
+    #[pyo3(signature = (uri="results.db"))]
+    pub fn new(uri: &str) -> Self {
+        Self { uri: uri.to_string() }
+    }
+
+    // persist the report as a new run, return its run_id
+    pub fn save(&self, report: &BacktestReport) -> Result<i64> {
+        let mut conn = self.connect()?;
+        Self::write(&mut conn, report)
+    }
+
+    pub fn load(&self, run_id: i64) -> Result<BacktestReport> {
+        let conn = self.connect()?;
+        Self::read(&conn, run_id)?.ok_or_else(|| Error::Data(format!("run {} not found", run_id)))
+    }
+
+    // (run_id, name, created_at) of all stored runs, oldest first
+    pub fn runs(&self) -> Result<Vec<(i64, String, String)>> {
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare("SELECT run_id, name, created_at::VARCHAR FROM bt_runs ORDER BY run_id")?;
+        let runs = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        Ok(runs.collect::<std::result::Result<Vec<_>, _>>()?)
+    }
+
+    pub fn delete(&self, run_id: i64) -> Result<()> {
+        let mut conn = self.connect()?;
+        let tx = conn.transaction()?;
+        for table in RESULT_TABLES {
+            tx.execute(&format!("DELETE FROM {} WHERE run_id = ?", table), params![run_id])?;
+        }
+        Ok(tx.commit()?)
+    }
+}
+
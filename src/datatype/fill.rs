@@ -0,0 +1,57 @@
+use crate::snapshot::impl_snapshot;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[pyclass(eq, eq_int, module = "bktrader.datatype")]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+// one executed deal, an exit may close several positions at once
+#[pyclass(module = "bktrader.datatype")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    #[pyo3(get)]
+    pub id: u32,
+    #[pyo3(get)]
+    pub dt: i32,
+    #[pyo3(get)]
+    pub side: Side,
+    #[pyo3(get)]
+    pub price: f64,
+    #[pyo3(get)]
+    pub volume: f64,
+    #[pyo3(get)]
+    pub fees: f64,
+    #[pyo3(get)]
+    pub position_ids: Vec<u32>,
+}
+
+#[pymethods]
+impl Fill {
+    #[new]
+    pub fn new(id: u32, dt: i32, side: Side, price: f64, volume: f64, fees: f64, position_ids: Vec<u32>) -> Self {
+        Self {
+            id,
+            dt,
+            side,
+            price,
+            volume,
+            fees,
+            position_ids,
+        }
+    }
+
+    #[getter]
+    pub fn amount(&self) -> f64 {
+        self.price * self.volume
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl_snapshot!(Side, Fill);
@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+pub mod fill;
 pub mod position;
 pub mod quote;
 
@@ -7,5 +8,10 @@ pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
     datatype.add_class::<quote::Bar>()?;
     datatype.add_class::<quote::BarM>()?;
     datatype.add_class::<position::Position>()?;
+    datatype.add_class::<position::PositionStatus>()?;
+    datatype.add_class::<fill::Fill>()?;
+    datatype.add_class::<fill::Side>()?;
+    // register as bktrader.datatype so that pickle can import the classes
+    parent_module.py().import("sys")?.getattr("modules")?.set_item("bktrader.datatype", &datatype)?;
     parent_module.add_submodule(&datatype)
 }
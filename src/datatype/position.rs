@@ -1,14 +1,16 @@
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass(eq, eq_int)]
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[pyclass(eq, eq_int, module = "bktrader.datatype")]
+#[derive(Debug, PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum PositionStatus {
     Opened,
     Closed,
 }
 
-#[pyclass]
-#[derive(Debug, Clone, Copy)]
+#[pyclass(module = "bktrader.datatype")]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Position {
     #[pyo3(get)]
     pub id: u32,
@@ -34,6 +36,20 @@ pub struct Position {
     pub fees: f64,
     #[pyo3(get)]
     pub holding_days: u32,
+    // maximum adverse excursion: lowest low while opened
+    #[pyo3(get)]
+    pub mae_price: f64,
+    #[pyo3(get)]
+    pub mae_pct: f64,
+    #[pyo3(get)]
+    pub mae_dt: i32,
+    // maximum favorable excursion: highest high while opened
+    #[pyo3(get)]
+    pub mfe_price: f64,
+    #[pyo3(get)]
+    pub mfe_pct: f64,
+    #[pyo3(get)]
+    pub mfe_dt: i32,
 }
 
 #[pymethods]
@@ -53,6 +69,25 @@ impl Position {
             pnl: 0.0,
             fees: 0.0,
             holding_days: 0,
+            mae_price: entry_price,
+            mae_pct: 0.0,
+            mae_dt: entry_dt,
+            mfe_price: entry_price,
+            mfe_pct: 0.0,
+            mfe_dt: entry_dt,
+        }
+    }
+
+    pub fn update_excursion(&mut self, dt: i32, high: f64, low: f64) {
+        if low < self.mae_price {
+            self.mae_price = low;
+            self.mae_pct = low / self.entry_price - 1.0;
+            self.mae_dt = dt;
+        }
+        if high > self.mfe_price {
+            self.mfe_price = high;
+            self.mfe_pct = high / self.entry_price - 1.0;
+            self.mfe_dt = dt;
         }
     }
 
@@ -60,3 +95,5 @@ impl Position {
         format!("{:?}", self)
     }
 }
+
+impl_snapshot!(PositionStatus, Position);
@@ -1,7 +1,9 @@
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
-#[derive(Debug)]
+#[pyclass(module = "bktrader.datatype")]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Bar {
     #[pyo3(get)]
     pub code: u32,
@@ -55,8 +57,16 @@ impl Bar {
     }
 }
 
-#[pyclass]
-#[derive(Debug)]
+impl Bar {
+    // traded amount / volume, None for a bar without trades where nothing can be filled
+    pub fn vwap(&self) -> Option<f64> {
+        let vwap = self.amount / self.volume;
+        (vwap.is_finite() && vwap > 0.0).then_some(vwap)
+    }
+}
+
+#[pyclass(module = "bktrader.datatype")]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BarM {
     #[pyo3(get)]
     pub code: u32,
@@ -104,6 +114,8 @@ impl BarM {
     }
 }
 
+impl_snapshot!(Bar, BarM);
+
 // Tick
 #[allow(dead_code)]
 pub struct Tick {
@@ -0,0 +1,100 @@
+use duckdb::arrow::error::ArrowError;
+use parquet::errors::ParquetError;
+use pyo3::exceptions::{PyArithmeticError, PyIOError, PyNotImplementedError, PyValueError};
+use pyo3::PyErr;
+use std::fmt;
+
+// every fallible path returns this instead of panicking, so a bad code or parameter set
+// fails on its own and a batch run over many codes keeps going.
+// python sees builtin exceptions: ValueError for bad input, IOError for storage, ...
+#[derive(Debug)]
+pub enum Error {
+    // bad constructor arguments, e.g. unknown ma_type or a zero period
+    InvalidParameter(String),
+    // rejected broker operations, e.g. non-positive price or exiting a closed position
+    InvalidOrder(String),
+    // malformed data, e.g. a snapshot or report that cannot be decoded
+    Data(String),
+    // numerical failures, e.g. a singular least-squares system
+    Numeric(String),
+    Unsupported(String),
+    Io(std::io::Error),
+    Db(duckdb::Error),
+    Parquet(ParquetError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidParameter(msg) => write!(f, "invalid parameter: {}", msg),
+            Error::InvalidOrder(msg) => write!(f, "invalid order: {}", msg),
+            Error::Data(msg) => write!(f, "invalid data: {}", msg),
+            Error::Numeric(msg) => write!(f, "numeric error: {}", msg),
+            Error::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Db(e) => write!(f, "duckdb error: {}", e),
+            Error::Parquet(e) => write!(f, "parquet error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Db(e) => Some(e),
+            Error::Parquet(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<duckdb::Error> for Error {
+    fn from(e: duckdb::Error) -> Self {
+        Error::Db(e)
+    }
+}
+
+impl From<ParquetError> for Error {
+    fn from(e: ParquetError) -> Self {
+        Error::Parquet(e)
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(e: ArrowError) -> Self {
+        Error::Data(e.to_string())
+    }
+}
+
+impl From<bincode::Error> for Error {
+    fn from(e: bincode::Error) -> Self {
+        Error::Data(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Data(e.to_string())
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        let msg = e.to_string();
+        match e {
+            Error::InvalidParameter(_) | Error::InvalidOrder(_) | Error::Data(_) => PyValueError::new_err(msg),
+            Error::Numeric(_) => PyArithmeticError::new_err(msg),
+            Error::Unsupported(_) => PyNotImplementedError::new_err(msg),
+            Error::Io(_) | Error::Db(_) | Error::Parquet(_) => PyIOError::new_err(msg),
+        }
+    }
+}
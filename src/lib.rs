@@ -1,10 +1,16 @@
+// indicator names follow trading conventions (SMA, ATR, ...), and pyo3 constructors mirror python keyword arguments
+#![allow(clippy::upper_case_acronyms, clippy::too_many_arguments)]
 use pyo3::prelude::*;
 // mod backtest;
 mod broker;
 pub mod datatype;
+pub mod error;
+mod snapshot;
 pub mod strategy;
 mod ta;
 
+pub use error::{Error, Result};
+
 #[pymodule]
 fn bktrader(m: &Bound<'_, PyModule>) -> PyResult<()> {
     datatype::register(m)?;
@@ -55,21 +55,21 @@ impl<T: FromRow + std::fmt::Debug> Engine<T> {
         }
     }
 
-    fn run<S: QuoteHandler<T>>(&mut self, stg: &mut S) -> Result<(), duckdb::Error> {
+    fn run<S: QuoteHandler<T>>(&mut self, stg: &mut S) -> bktrader::Result<()> {
         let conn = Connection::open(&self.uri)?;
         let mut stmt = conn.prepare(&self.sql)?;
         let rows = stmt.query_map(params![self.code, self.start, self.end], |row| T::from_row(row))?;
 
         for row in rows {
             let data = row?;
-            stg.on_quote(&data);
+            stg.on_quote(&data)?;
         }
 
         Ok(())
     }
 }
 
-fn main() -> Result<(), duckdb::Error> {
+fn main() -> bktrader::Result<()> {
     // Define the database URI
     let uri = "etf.db";
     // Define the date range
@@ -141,10 +141,13 @@ fn main() -> Result<(), duckdb::Error> {
 
     let start_time = Instant::now();
     code_list.par_iter().for_each(|&code| {
-        let mut stga = GridCCI::new(1e5, 15, 20, 0.0, 0.3, "sma", 0.3, 25, 0.15, -0.1);
-        let mut engine = Engine::<Bar>::new(uri, bar_sql, code, start, end);
-        if let Err(e) = engine.run(&mut stga) {
-            eprintln!("Error processing Bar code {}: {:?}", code, e);
+        // a failing code is reported and skipped, the others keep running
+        let result = GridCCI::new(1e5, 15, 20, 0.0, 0.3, "sma", 0.3, 25, 0.15, -0.1).and_then(|mut stga| {
+            let mut engine = Engine::<Bar>::new(uri, bar_sql, code, start, end);
+            engine.run(&mut stga)
+        });
+        if let Err(e) = result {
+            eprintln!("Error processing Bar code {}: {}", code, e);
         }
         // println!("active length of {} is {}", code, stga.broker.active_position_len());
         // println!("profit of {} is {}", code, stga.broker.profit_float());
@@ -1,11 +1,30 @@
+from bktrader import broker
+
+
 class BacktestEngine:
-    def __init__(self, replayer, strategy):
+    def __init__(self, replayer, strategy, params=None):
         self.replayer = replayer
         self.strategy = strategy
+        self.params = params or {}
 
     def run(self):
         for quote in self.replayer:
             self.strategy.on_update(quote)
+        return self.report()
+
+    def report(self, risk_free_rate=0.015, mar=0.01):
+        return broker.BacktestReport(self.strategy.broker, type(self.strategy).__name__, self.params, risk_free_rate, mar)
+
+
+def save_state(obj, path):
+    # persist strategy/broker/indicator state after the daily close
+    with open(path, "wb") as f:
+        f.write(obj.to_bytes())
+
+
+def load_state(cls, path):
+    with open(path, "rb") as f:
+        return cls.from_bytes(f.read())
 
 
 class TradeEngine:
@@ -17,7 +36,19 @@ class TradeEngine:
     def run(self):
         for quote in self.replayer:
             self.strategy.on_update(quote)
-        self.strategy.on_update(self.last_quote)
+        # today's bar is built from a live quote, keep it provisional until the close
+        self.strategy.on_provisional(self.last_quote)
+
+    def refresh(self, last_quote):
+        # replace today's provisional bar with a newer quote, history is not replayed
+        self.last_quote = last_quote
+        self.strategy.on_provisional(last_quote)
+
+    def close(self, final_quote=None):
+        if final_quote is None:
+            self.strategy.commit()
+        else:
+            self.strategy.on_update(final_quote)
 
 
 if __name__ == "__main__":
@@ -30,10 +61,14 @@ if __name__ == "__main__":
     # stg = strategy.DMAStrategy(init_cash=5e4)
     stg = strategy.GridPercent(init_cash=5e4, band_mult=2, max_active_pos_len=10)
     # stg = strategy.GridATR(init_cash=5e4, band_mult=1.5, max_active_pos_len=10)
-    engine = BacktestEngine(replayer, stg)
+    engine = BacktestEngine(replayer, stg, params={"init_cash": 5e4, "band_mult": 2, "max_active_pos_len": 10})
     start_time = time.time()
-    engine.run()
+    report = engine.run()
     print(f"cost {time.time()-start_time:.2f}s")
     print(f"last portfolio: {stg.broker.portfolio_value}")
     print(f"total_fees: {stg.broker.total_fees}")
     print(f"actives: {stg.broker.active_position_len()}")
+    print(f"metrics: {report.metrics}")
+    report.save_parquet("report.parquet")
+    run_id = broker.ResultStore("results.db").save(report)
+    print(f"saved as run {run_id}")
@@ -2,14 +2,12 @@ from flaml import AutoML
 from sklearn import metrics
 import duckdb
 import polars as pl
-from scipy import signal
 import datetime as dt
+import sys
+from pathlib import Path
 
-
-def prepare_savgol(window_length, polyorder=2) -> tuple:
-    s_deriv1 = pl.Series(signal.savgol_coeffs(window_length, polyorder, deriv=1, delta=1, pos=window_length - 1, use="dot"))
-    s_deriv2 = pl.Series(signal.savgol_coeffs(window_length, polyorder, deriv=2, delta=1, pos=window_length - 1, use="dot"))
-    return s_deriv1, s_deriv2
+sys.path.append(str(Path(__file__).resolve().parents[1]))
+import ta_expr  # noqa: F401, E402, registers pl.Expr.ta
 
 
 def query_sector_codes(uri: str, sectors: list[int]) -> list[int]:
@@ -56,20 +54,22 @@ def prepare_dataset(uri: str, start_dt: dt.date, end_dt: dt.date, codes: list[in
             .drop_nans()
         )
 
-    s_deriv1, s_deriv2 = prepare_savgol(history_days)
+    def deriv(col: str, field: str) -> pl.Expr:
+        return pl.col(col).ta.savgol(history_days).over("code").struct.field(field)
+
     return (
         df.with_columns(
-            pl.col("adjvwap").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("vwap_deriv1"),
-            pl.col("adjvwap").rolling_map(lambda s: s.dot(s_deriv2), window_size=history_days).over("code").alias("vwap_deriv2"),
-            pl.col("adjvol").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("adjvol_deriv1"),
-            pl.col("adjvol").rolling_map(lambda s: s.dot(s_deriv2), window_size=history_days).over("code").alias("adjvol_deriv2"),
-            pl.col("boxp").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("boxp_deriv1"),
-            pl.col("vdo").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("vdo_deriv1"),
-            pl.col("vdp").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("vdp_deriv1"),
-            pl.col("odp").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("odp_deriv1"),
-            pl.col("tr").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("tr_deriv1"),
-            pl.col("turnover").rolling_map(lambda s: s.dot(s_deriv1), window_size=history_days).over("code").alias("turnover_deriv1"),
-            pl.col("turnover").rolling_map(lambda s: s.dot(s_deriv2), window_size=history_days).over("code").alias("turnover_deriv2"),
+            deriv("adjvwap", "deriv1").alias("vwap_deriv1"),
+            deriv("adjvwap", "deriv2").alias("vwap_deriv2"),
+            deriv("adjvol", "deriv1").alias("adjvol_deriv1"),
+            deriv("adjvol", "deriv2").alias("adjvol_deriv2"),
+            deriv("boxp", "deriv1").alias("boxp_deriv1"),
+            deriv("vdo", "deriv1").alias("vdo_deriv1"),
+            deriv("vdp", "deriv1").alias("vdp_deriv1"),
+            deriv("odp", "deriv1").alias("odp_deriv1"),
+            deriv("tr", "deriv1").alias("tr_deriv1"),
+            deriv("turnover", "deriv1").alias("turnover_deriv1"),
+            deriv("turnover", "deriv2").alias("turnover_deriv2"),
             ((pl.col(target_name) - pl.min(target_name).over("dti")) / (pl.max(target_name).over("dti") - pl.min(target_name).over("dti")) * 30).round().cast(pl.UInt32).alias("label"),
         )
         .sort("dti")
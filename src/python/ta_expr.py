@@ -0,0 +1,349 @@
+"""
+ta indicators as polars expressions, registered under the `ta` namespace:
+
+    import ta_expr  # noqa: F401, registers pl.Expr.ta
+    df.with_columns(
+        pl.col("close").ta.sma(20).over("code").alias("sma20"),
+        pl.col("high").ta.atr(pl.col("low"), pl.col("preclose"), 21).over("code").alias("atr21"),
+        pl.col("close").ta.kdj(pl.col("high"), pl.col("low")).over("code").alias("kdj"),  # struct {k, d, j}
+    )
+
+every group (over/group_by) gets a fresh indicator fed through its numpy batch(),
+so values equal feeding update() bar by bar. warmup NaN are returned as null.
+
+this is a python namespace over map_batches, not a compiled polars plugin: the
+indicators run in rust but each call goes through the python bridge.
+"""
+
+import polars as pl
+from bktrader import ta
+
+
+def _to_series(values, name: str) -> pl.Series:
+    # int outputs (e.g. Crosser) are widened to match the declared Float64 dtype
+    return pl.Series(name, values, dtype=pl.Float64).fill_nan(None)
+
+
+def _apply(factory, exprs: list, fields: list[str] | None = None) -> pl.Expr:
+    # single input is passed as is, multiple inputs are packed into a struct
+    def run(s: pl.Series) -> pl.Series:
+        inputs = [s.struct.field(f).cast(pl.Float64).to_numpy() for f in s.struct.fields] if s.dtype == pl.Struct else [s.cast(pl.Float64).to_numpy()]
+        outputs = factory().batch(*inputs)
+        if fields is None:
+            return _to_series(outputs, s.name)
+        return pl.struct([_to_series(values, field) for field, values in zip(fields, outputs)], eager=True).alias(s.name)
+
+    if fields is None:
+        return_dtype = pl.Float64
+    else:
+        return_dtype = pl.Struct({field: pl.Float64 for field in fields})
+    expr = exprs[0] if len(exprs) == 1 else pl.struct([e.alias(f"_{i}") for i, e in enumerate(exprs)])
+    return expr.map_batches(run, return_dtype=return_dtype).alias(exprs[0].meta.output_name(raise_if_undetermined=False) or "ta")
+
+
+_BAND_FIELDS = ["upper", "middle", "lower", "percent_b", "bandwidth"]
+_MACD_FIELDS = ["line", "signal", "histogram"]
+
+
+@pl.api.register_expr_namespace("ta")
+class TaNamespace:
+    def __init__(self, expr: pl.Expr):
+        self._expr = expr
+
+    # moving averages
+    def ma(self, period: int, method: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.MA(period, method), [self._expr])
+
+    def sma(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.SMA(period), [self._expr])
+
+    def wma(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.WMA(period), [self._expr])
+
+    def ema(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.EMA(period), [self._expr])
+
+    def dema(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.DEMA(period), [self._expr])
+
+    def hma(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.HMA(period), [self._expr])
+
+    def rma(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RMA(period), [self._expr])
+
+    def lsma(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.LSMA(period), [self._expr])
+
+    def alma(self, period: int, offset: float = 0.85, sigma: float = 6.0) -> pl.Expr:
+        return _apply(lambda: ta.ALMA(period, offset, sigma), [self._expr])
+
+    def vwma(self, volume: pl.Expr, period: int) -> pl.Expr:
+        return _apply(lambda: ta.VWMA(period), [self._expr, volume])
+
+    def tema(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.TEMA(period), [self._expr])
+
+    def zlema(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.ZLEMA(period), [self._expr])
+
+    def kama(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.KAMA(period), [self._expr])
+
+    def t3(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.T3(period), [self._expr])
+
+    def mcginley(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.McGinley(period), [self._expr])
+
+    def frama(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.FRAMA(period), [self._expr])
+
+    def vidya(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.VIDYA(period), [self._expr])
+
+    # volatility & momentum, self is the first argument of update()
+    def atr(self, low: pl.Expr, preclose: pl.Expr, period: int = 21, ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.ATR(period, ma_type), [self._expr, low, preclose])
+
+    def natr(self, low: pl.Expr, close: pl.Expr, preclose: pl.Expr, period: int = 21, ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.NATR(period, ma_type), [self._expr, low, close, preclose])
+
+    # annualized volatility, periods_per_year=1 keeps it per bar
+    def parkinson(self, low: pl.Expr, period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.Parkinson(period, periods_per_year), [self._expr, low])
+
+    def garman_klass(self, high: pl.Expr, low: pl.Expr, close: pl.Expr, period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.GarmanKlass(period, periods_per_year), [self._expr, high, low, close])
+
+    def rogers_satchell(self, high: pl.Expr, low: pl.Expr, close: pl.Expr, period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.RogersSatchell(period, periods_per_year), [self._expr, high, low, close])
+
+    def yang_zhang(self, high: pl.Expr, low: pl.Expr, close: pl.Expr, preclose: pl.Expr, period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.YangZhang(period, periods_per_year), [self._expr, high, low, close, preclose])
+
+    def hv(self, preclose: pl.Expr, period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.HistoricalVolatility(period, periods_per_year), [self._expr, preclose])
+
+    def garch(self, preclose: pl.Expr, window: int = 500, refit_period: int = 20, periods_per_year: float = 243.0) -> pl.Expr:
+        return _apply(lambda: ta.GARCH(window, refit_period, periods_per_year), [self._expr, preclose])
+
+    def cci(self, low: pl.Expr, close: pl.Expr, period: int = 14, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.CCI(period, ma_type), [self._expr, low, close])
+
+    def kdj(self, high: pl.Expr, low: pl.Expr, minmax_period: int = 9, ma_period: int = 3, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.KDJ(minmax_period, ma_period, ma_type), [self._expr, high, low], ["k", "d", "j"])
+
+    def rsi(self, period: int = 14, ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.RSI(period, ma_type), [self._expr])
+
+    def stoch(self, low: pl.Expr, close: pl.Expr, minmax_period: int = 14, k_period: int = 3, d_period: int = 3, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.Stoch(minmax_period, k_period, d_period, ma_type), [self._expr, low, close], ["k", "d"])
+
+    def stochrsi(self, rsi_period: int = 14, minmax_period: int = 14, k_period: int = 3, d_period: int = 3, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.StochRSI(rsi_period, minmax_period, k_period, d_period, ma_type), [self._expr], ["k", "d"])
+
+    def willr(self, low: pl.Expr, close: pl.Expr, period: int = 14) -> pl.Expr:
+        return _apply(lambda: ta.WilliamsR(period), [self._expr, low, close])
+
+    def roc(self, period: int = 10) -> pl.Expr:
+        return _apply(lambda: ta.ROC(period), [self._expr])
+
+    def cmo(self, period: int = 14) -> pl.Expr:
+        return _apply(lambda: ta.CMO(period), [self._expr])
+
+    def tsi(self, long_period: int = 25, short_period: int = 13, signal_period: int = 13, ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.TSI(long_period, short_period, signal_period, ma_type), [self._expr], ["tsi", "signal"])
+
+    def ultosc(self, low: pl.Expr, close: pl.Expr, preclose: pl.Expr, fast_period: int = 7, mid_period: int = 14, slow_period: int = 28) -> pl.Expr:
+        return _apply(lambda: ta.UltimateOscillator(fast_period, mid_period, slow_period), [self._expr, low, close, preclose])
+
+    # macd family, struct {line, signal, histogram}
+    def macd(self, fast_period: int = 12, slow_period: int = 26, signal_period: int = 9, ma_type: str = "ema", signal_ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.MACD(fast_period, slow_period, signal_period, ma_type, signal_ma_type), [self._expr], _MACD_FIELDS)
+
+    def apo(self, fast_period: int = 12, slow_period: int = 26, signal_period: int = 9, ma_type: str = "sma", signal_ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.APO(fast_period, slow_period, signal_period, ma_type, signal_ma_type), [self._expr], _MACD_FIELDS)
+
+    def ppo(self, fast_period: int = 12, slow_period: int = 26, signal_period: int = 9, ma_type: str = "sma", signal_ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.PPO(fast_period, slow_period, signal_period, ma_type, signal_ma_type), [self._expr], _MACD_FIELDS)
+
+    def trix(self, period: int = 15, signal_period: int = 9, ma_type: str = "ema", signal_ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.TRIX(period, signal_period, ma_type, signal_ma_type), [self._expr], _MACD_FIELDS)
+
+    # trend strength
+    def adx(self, low: pl.Expr, preclose: pl.Expr, period: int = 14, ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.ADX(period, ma_type), [self._expr, low, preclose], ["plus_di", "minus_di", "adx"])
+
+    def aroon(self, low: pl.Expr, period: int = 14) -> pl.Expr:
+        return _apply(lambda: ta.Aroon(period), [self._expr, low], ["up", "down", "osc"])
+
+    def psar(self, low: pl.Expr, af_start: float = 0.02, af_step: float = 0.02, af_max: float = 0.2) -> pl.Expr:
+        return _apply(lambda: ta.PSAR(af_start, af_step, af_max), [self._expr, low])
+
+    def supertrend(self, low: pl.Expr, close: pl.Expr, preclose: pl.Expr, period: int = 10, multiplier: float = 3.0, ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.SuperTrend(period, multiplier, ma_type), [self._expr, low, close, preclose], ["supertrend", "direction"])
+
+    def vortex(self, low: pl.Expr, preclose: pl.Expr, period: int = 14) -> pl.Expr:
+        return _apply(lambda: ta.Vortex(period), [self._expr, low, preclose], ["plus_vi", "minus_vi"])
+
+    # Hilbert transform cycle suite, ported from TA-Lib HT_* and MAMA
+    def ht_phasor(self) -> pl.Expr:
+        return _apply(lambda: ta.HtPhasor(), [self._expr], ["inphase", "quadrature"])
+
+    def ht_dcperiod(self) -> pl.Expr:
+        return _apply(lambda: ta.HtDCPeriod(), [self._expr])
+
+    def ht_dcphase(self) -> pl.Expr:
+        return _apply(lambda: ta.HtDCPhase(), [self._expr])
+
+    def ht_sine(self) -> pl.Expr:
+        return _apply(lambda: ta.HtSine(), [self._expr], ["sine", "leadsine"])
+
+    def ht_trendline(self) -> pl.Expr:
+        return _apply(lambda: ta.HtTrendline(), [self._expr])
+
+    def ht_trendmode(self) -> pl.Expr:
+        return _apply(lambda: ta.HtTrendMode(), [self._expr])
+
+    def mama(self, fast_limit: float = 0.5, slow_limit: float = 0.05) -> pl.Expr:
+        return _apply(lambda: ta.MAMA(fast_limit, slow_limit), [self._expr], ["mama", "fama"])
+
+    # Ehlers filters
+    def super_smoother(self, period: int = 10) -> pl.Expr:
+        return _apply(lambda: ta.SuperSmoother(period), [self._expr])
+
+    def roofing(self, hp_period: int = 48, lp_period: int = 10) -> pl.Expr:
+        return _apply(lambda: ta.Roofing(hp_period, lp_period), [self._expr])
+
+    def itrend(self, alpha: float = 0.07) -> pl.Expr:
+        return _apply(lambda: ta.ITrend(alpha), [self._expr], ["itrend", "trigger"])
+
+    # bands, struct {upper, middle, lower, percent_b, bandwidth}
+    def bollinger(self, period: int = 20, mult: float = 2.0, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.Bollinger(period, mult, ma_type), [self._expr], _BAND_FIELDS)
+
+    def keltner(self, low: pl.Expr, close: pl.Expr, preclose: pl.Expr, ma_period: int = 20, atr_period: int = 10, mult: float = 2.0, ma_type: str = "ema", atr_ma_type: str = "rma") -> pl.Expr:
+        return _apply(lambda: ta.Keltner(ma_period, atr_period, mult, ma_type, atr_ma_type), [self._expr, low, close, preclose], _BAND_FIELDS)
+
+    def donchian(self, low: pl.Expr, close: pl.Expr, period: int = 20) -> pl.Expr:
+        return _apply(lambda: ta.Donchian(period), [self._expr, low, close], _BAND_FIELDS)
+
+    def envelope(self, period: int = 20, percent: float = 2.5, ma_type: str = "sma") -> pl.Expr:
+        return _apply(lambda: ta.Envelope(period, percent, ma_type), [self._expr], _BAND_FIELDS)
+
+    # volume, self is the first price argument of update()
+    def obv(self, preclose: pl.Expr, volume: pl.Expr) -> pl.Expr:
+        return _apply(lambda: ta.OBV(), [self._expr, preclose, volume])
+
+    def mfi(self, low: pl.Expr, close: pl.Expr, volume: pl.Expr, period: int = 14) -> pl.Expr:
+        return _apply(lambda: ta.MFI(period), [self._expr, low, close, volume])
+
+    def ad(self, low: pl.Expr, close: pl.Expr, volume: pl.Expr) -> pl.Expr:
+        return _apply(lambda: ta.AD(), [self._expr, low, close, volume])
+
+    def cmf(self, low: pl.Expr, close: pl.Expr, volume: pl.Expr, period: int = 20) -> pl.Expr:
+        return _apply(lambda: ta.CMF(period), [self._expr, low, close, volume])
+
+    def force_index(self, preclose: pl.Expr, volume: pl.Expr, period: int = 13, ma_type: str = "ema") -> pl.Expr:
+        return _apply(lambda: ta.ForceIndex(period, ma_type), [self._expr, preclose, volume])
+
+    def eom(self, low: pl.Expr, volume: pl.Expr, period: int = 14, ma_type: str = "sma", divisor: float = 1e8) -> pl.Expr:
+        return _apply(lambda: ta.EaseOfMovement(period, ma_type, divisor), [self._expr, low, volume])
+
+    # anchor restarts the average where true, e.g. pl.col("dt") == date or pl.col("dt").dt.month() != pl.col("dt").shift().dt.month()
+    def anchored_vwap(self, volume: pl.Expr, anchor: pl.Expr | None = None) -> pl.Expr:
+        anchor = pl.lit(False) if anchor is None else anchor.fill_null(False)
+        return _apply(lambda: ta.AnchoredVWAP(), [self._expr, volume, anchor])
+
+    # savgol value and derivatives at pos (default the newest point), same as scipy savgol_coeffs(period, polyorder, deriv, delta, pos, use="dot")
+    def savgol(self, period: int, polyorder: int = 2, pos: int | None = None, delta: float = 1.0, min_periods: int | None = None) -> pl.Expr:
+        fields = ["value"] + [f"deriv{d}" for d in range(1, polyorder + 1)]
+        return _apply(lambda: ta.Savgol(period, polyorder, pos, delta, min_periods), [self._expr], fields)
+
+    # non-causal centered filter, same as scipy.signal.savgol_filter, the edges look ahead
+    def savgol_filter(self, window_length: int, polyorder: int, deriv: int = 0, delta: float = 1.0, mode: str = "interp", cval: float = 0.0) -> pl.Expr:
+        def run(s: pl.Series) -> pl.Series:
+            return _to_series(ta.Savgol.filter(s.cast(pl.Float64).to_numpy(), window_length, polyorder, deriv, delta, mode, cval), s.name)
+
+        return self._expr.map_batches(run, return_dtype=pl.Float64)
+
+    # rolling statistics
+    def rolling_sum(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingSum(period), [self._expr])
+
+    def rolling_mean(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingMean(period), [self._expr])
+
+    def rolling_max(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingMax(period), [self._expr])
+
+    def rolling_min(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingMin(period), [self._expr])
+
+    # bars since the window max/min, 0 is the current bar
+    def rolling_argmax(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingArgMax(period), [self._expr])
+
+    def rolling_argmin(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingArgMin(period), [self._expr])
+
+    def rolling_minmax(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingMinMax(period), [self._expr], ["min", "max", "argmin", "argmax"])
+
+    def rolling_quantile(self, period: int, quantile: float) -> pl.Expr:
+        return _apply(lambda: ta.RollingQuantile(period, quantile), [self._expr])
+
+    def rolling_rank(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingRank(period), [self._expr])
+
+    def rolling_var(self, period: int, ddof: int = 1) -> pl.Expr:
+        return _apply(lambda: ta.RollingVariance(period, ddof), [self._expr])
+
+    def rolling_std(self, period: int, ddof: int = 1) -> pl.Expr:
+        return _apply(lambda: ta.RollingStdev(period, ddof), [self._expr])
+
+    def rolling_skew(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingSkew(period), [self._expr])
+
+    def rolling_kurt(self, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingKurt(period), [self._expr])
+
+    def rolling_zscore(self, period: int, ddof: int = 1) -> pl.Expr:
+        return _apply(lambda: ta.RollingZScore(period, ddof), [self._expr])
+
+    # co-movement with a second series, self is y and other the regressor x for beta and linreg
+    def rolling_cov(self, other: pl.Expr, period: int, ddof: int = 1) -> pl.Expr:
+        return _apply(lambda: ta.RollingCovariance(period, ddof), [self._expr, other])
+
+    def rolling_corr(self, other: pl.Expr, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingCorrelation(period), [self._expr, other])
+
+    def rolling_beta(self, benchmark: pl.Expr, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingBeta(period), [self._expr, benchmark])
+
+    def rolling_linreg(self, x: pl.Expr, period: int) -> pl.Expr:
+        return _apply(lambda: ta.RollingLinReg(period), [self._expr, x], ["slope", "intercept", "r2"])
+
+    # cumulative statistics from the first bar of the group
+    def cum_min(self) -> pl.Expr:
+        return _apply(lambda: ta.CumMin(), [self._expr])
+
+    def cum_max(self) -> pl.Expr:
+        return _apply(lambda: ta.CumMax(), [self._expr])
+
+    def cum_minmax(self, init_min: float = float("inf"), init_max: float = float("-inf")) -> pl.Expr:
+        return _apply(lambda: ta.CumMinMax(init_min, init_max), [self._expr], ["min", "max"])
+
+    def cum_mean(self) -> pl.Expr:
+        return _apply(lambda: ta.CumMean(), [self._expr])
+
+    def cum_median(self) -> pl.Expr:
+        return _apply(lambda: ta.CumMedian(), [self._expr])
+
+    def cum_quantile(self, quantile: float) -> pl.Expr:
+        return _apply(lambda: ta.CumQuantile(quantile), [self._expr])
+
+    # 1 when self crosses above other, -1 when it crosses below, 0 otherwise
+    def cross(self, other: pl.Expr) -> pl.Expr:
+        return _apply(lambda: ta.Crosser(), [self._expr, other])
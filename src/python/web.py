@@ -10,7 +10,7 @@ from fastapi.security import HTTPBasic, HTTPBasicCredentials
 from fastapi.templating import Jinja2Templates
 from fastapi.staticfiles import StaticFiles
 
-from bktrader import strategy
+from bktrader import strategy, broker
 from draw import backtest_history, backtest_realtime
 from quote.realtime import XueQiuQuote, EastEtfQuote, EastLofQuote
 from quote.history import DuckBatchReplayer
@@ -31,6 +31,13 @@ templates = Jinja2Templates(directory="templates")
 # add static js or css
 app.mount("/static", StaticFiles(directory="static"), name="static")
 
+SUMMARY_METRICS = ["profit_net", "max_drawdown", "sharpe_annual", "sharpe_volatility", "sharpe_ratio", "sortino_annual", "sortino_volatility", "sortino_ratio"]
+
+
+def report_metrics(stg):
+    report = broker.BacktestReport(stg.broker, type(stg).__name__, risk_free_rate=0.015, mar=0.01)
+    return {k: round(v, 3) for k, v in report.metrics.items()}
+
 
 def get_current_username(credentials: Annotated[HTTPBasicCredentials, Depends(security)]):
     current_username_bytes = credentials.username.encode("utf8")
@@ -67,26 +74,18 @@ async def render_etf_history(
     name, mer, cer = ETF_INFO_DICT.get(code, (None, None, None))
     chart = backtest_history(code, start, end, stg, ETF_DB_URI, title=f"{code} {name}")
 
-    (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-    (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
-    avg_hold_days = round(stg.broker.avg_hold_days(), 3)
+    metrics = report_metrics(stg)
 
     return templates.TemplateResponse(
         request=request,
         name="history/single.html",
         context={
             "code": code,
-            "portfolio_profit": round(stg.broker.profit_net(), 3),
-            "max_drawdown": round(stg.broker.analyzer.max_drawdown(), 3),
-            "sharpe_annual": round(sharpe_annual, 3),
-            "sharpe_volatility": round(sharpe_volatility, 3),
-            "sharpe_ratio": round(sharpe_ratio, 3),
-            "sortino_annual": round(sortino_annual, 3),
-            "sortino_volatility": round(sortino_volatility, 3),
-            "sortino_ratio": round(sortino_ratio, 3),
+            "portfolio_profit": metrics["profit_net"],
+            **{k: metrics[k] for k in SUMMARY_METRICS[1:]},
             "mer": mer,
             "cer": cer,
-            "avg_hold_days": avg_hold_days,
+            "avg_hold_days": metrics["avg_hold_days"],
             "candles": chart.render_embed(),
         },
     )
@@ -113,26 +112,18 @@ async def render_lof_history(
     name, mer, cer = LOF_INFO_DICT.get(code, (None, None, None))
     chart = backtest_history(code, start, end, stg, LOF_DB_URI, title=f"{code} {name}")
 
-    (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-    (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
-    avg_hold_days = round(stg.broker.avg_hold_days(), 3)
+    metrics = report_metrics(stg)
 
     return templates.TemplateResponse(
         request=request,
         name="history/single.html",
         context={
             "code": code,
-            "portfolio_profit": round(stg.broker.profit_net(), 3),
-            "max_drawdown": round(stg.broker.analyzer.max_drawdown(), 3),
-            "sharpe_annual": round(sharpe_annual, 3),
-            "sharpe_volatility": round(sharpe_volatility, 3),
-            "sharpe_ratio": round(sharpe_ratio, 3),
-            "sortino_annual": round(sortino_annual, 3),
-            "sortino_volatility": round(sortino_volatility, 3),
-            "sortino_ratio": round(sortino_ratio, 3),
+            "portfolio_profit": metrics["profit_net"],
+            **{k: metrics[k] for k in SUMMARY_METRICS[1:]},
             "mer": mer,
             "cer": cer,
-            "avg_hold_days": avg_hold_days,
+            "avg_hold_days": metrics["avg_hold_days"],
             "candles": chart.render_embed(),
         },
     )
@@ -161,26 +152,18 @@ async def render_etf_realtime(
     discount = round((quoter.quote["current"] / quoter.quote["iopv"] - 1) * 100, 3)
     chart = backtest_realtime(code, start, last_quote, stg, ETF_DB_URI, title=f"{code} {name}")
 
-    (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-    (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
-    avg_hold_days = round(stg.broker.avg_hold_days(), 3)
+    metrics = report_metrics(stg)
 
     return templates.TemplateResponse(
         request=request,
         name="realtime/single.html",
         context={
             "code": code,
-            "portfolio_profit": round(stg.broker.profit_net(), 3),
-            "max_drawdown": round(stg.broker.analyzer.max_drawdown(), 3),
-            "sharpe_annual": round(sharpe_annual, 3),
-            "sharpe_volatility": round(sharpe_volatility, 3),
-            "sharpe_ratio": round(sharpe_ratio, 3),
-            "sortino_annual": round(sortino_annual, 3),
-            "sortino_volatility": round(sortino_volatility, 3),
-            "sortino_ratio": round(sortino_ratio, 3),
+            "portfolio_profit": metrics["profit_net"],
+            **{k: metrics[k] for k in SUMMARY_METRICS[1:]},
             "mer": mer,
             "cer": cer,
-            "avg_hold_days": avg_hold_days,
+            "avg_hold_days": metrics["avg_hold_days"],
             "discount": discount,
             "candles": chart.render_embed(),
         },
@@ -209,26 +192,18 @@ async def render_lof_realtime(
     last_quote = quoter.get_quote(code)
     chart = backtest_realtime(code, start, last_quote, stg, LOF_DB_URI, title=f"{code} {name}")
 
-    (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-    (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
-    avg_hold_days = round(stg.broker.avg_hold_days(), 3)
+    metrics = report_metrics(stg)
 
     return templates.TemplateResponse(
         request=request,
         name="realtime/single.html",
         context={
             "code": code,
-            "portfolio_profit": round(stg.broker.profit_net(), 3),
-            "max_drawdown": round(stg.broker.analyzer.max_drawdown(), 3),
-            "sharpe_annual": round(sharpe_annual, 3),
-            "sharpe_volatility": round(sharpe_volatility, 3),
-            "sharpe_ratio": round(sharpe_ratio, 3),
-            "sortino_annual": round(sortino_annual, 3),
-            "sortino_volatility": round(sortino_volatility, 3),
-            "sortino_ratio": round(sortino_ratio, 3),
+            "portfolio_profit": metrics["profit_net"],
+            **{k: metrics[k] for k in SUMMARY_METRICS[1:]},
             "mer": mer,
             "cer": cer,
-            "avg_hold_days": avg_hold_days,
+            "avg_hold_days": metrics["avg_hold_days"],
             "discount": float("nan"),
             "candles": chart.render_embed(),
         },
@@ -275,22 +250,14 @@ async def bench_etf_history(
     data = []
     for code in stgs:
         stg = stgs[code]
-        (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-        (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
+        metrics = report_metrics(stg)
         name, mer, cer = ETF_INFO_DICT.get(code, (None, None, None))
         row = [
             code,
             name,
             mer,
             cer,
-            round(stg.broker.profit_net(), 3),
-            round(stg.broker.analyzer.max_drawdown(), 3),
-            round(sharpe_annual, 3),
-            round(sharpe_volatility, 3),
-            round(sharpe_ratio, 3),
-            round(sortino_annual, 3),
-            round(sortino_volatility, 3),
-            round(sortino_ratio, 3),
+            *[metrics[k] for k in SUMMARY_METRICS],
         ]
         data.append(row)
 
@@ -336,22 +303,14 @@ async def bench_lof_history(
     data = []
     for code in stgs:
         stg = stgs[code]
-        (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-        (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
+        metrics = report_metrics(stg)
         name, mer, cer = LOF_INFO_DICT.get(code, (None, None, None))
         row = [
             code,
             name,
             mer,
             cer,
-            round(stg.broker.profit_net(), 3),
-            round(stg.broker.analyzer.max_drawdown(), 3),
-            round(sharpe_annual, 3),
-            round(sharpe_volatility, 3),
-            round(sharpe_ratio, 3),
-            round(sortino_annual, 3),
-            round(sortino_volatility, 3),
-            round(sortino_ratio, 3),
+            *[metrics[k] for k in SUMMARY_METRICS],
         ]
         data.append(row)
 
@@ -398,8 +357,7 @@ async def etf_available(
 
         last_position = stg.broker.position_last()
         if last_position:
-            (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-            (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
+            metrics = report_metrics(stg)
             name, mer, cer = ETF_INFO_DICT.get(code, (None, None, None))
             row = [
                 code,
@@ -408,14 +366,7 @@ async def etf_available(
                 (dt.date(1970, 1, 1) + dt.timedelta(days=last_position.exit_dt)).isoformat() if last_position.exit_dt else None,
                 mer,
                 cer,
-                round(stg.broker.profit_net(), 3),
-                round(stg.broker.analyzer.max_drawdown(), 3),
-                round(sharpe_annual, 3),
-                round(sharpe_volatility, 3),
-                round(sharpe_ratio, 3),
-                round(sortino_annual, 3),
-                round(sortino_volatility, 3),
-                round(sortino_ratio, 3),
+                *[metrics[k] for k in SUMMARY_METRICS],
             ]
             data.append(row)
 
@@ -461,8 +412,7 @@ async def lof_available(
         stg = stgs[code]
         last_position = stg.broker.position_last()
         if last_position:
-            (sharpe_annual, sharpe_volatility, sharpe_ratio) = stg.broker.analyzer.sharpe_ratio(0.015)
-            (sortino_annual, sortino_volatility, sortino_ratio) = stg.broker.analyzer.sortino_ratio(0.015, 0.01)
+            metrics = report_metrics(stg)
             name, mer, cer = LOF_INFO_DICT.get(code, (None, None, None))
             row = [
                 code,
@@ -471,14 +421,7 @@ async def lof_available(
                 (dt.date(1970, 1, 1) + dt.timedelta(days=last_position.exit_dt)).isoformat() if last_position.exit_dt else None,
                 mer,
                 cer,
-                round(stg.broker.profit_net(), 3),
-                round(stg.broker.analyzer.max_drawdown(), 3),
-                round(sharpe_annual, 3),
-                round(sharpe_volatility, 3),
-                round(sharpe_ratio, 3),
-                round(sortino_annual, 3),
-                round(sortino_volatility, 3),
-                round(sortino_ratio, 3),
+                *[metrics[k] for k in SUMMARY_METRICS],
             ]
             data.append(row)
 
@@ -0,0 +1,141 @@
+use crate::error::Result;
+use serde::{de::DeserializeOwned, Serialize};
+
+// state of brokers, indicators and strategies can be persisted after the close and restored next day
+// bytes are bincode (compact and exact), json is for inspection and interop
+pub fn to_bytes<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(value)?)
+}
+
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    Ok(bincode::deserialize(bytes)?)
+}
+
+pub fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T> {
+    Ok(serde_json::from_str(json)?)
+}
+
+// json has no NaN or infinity, so warmup buffers write them as null and read null back as NaN.
+// every indicator treats a NaN seed like an infinite one (f64::max/min skip NaN), bytes stay exact.
+pub mod nan_as_null {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            Some(*value).filter(|v| v.is_finite()).serialize(serializer)
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+        } else {
+            f64::deserialize(deserializer)
+        }
+    }
+}
+
+pub mod nan_vec_as_null {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(values.iter().map(|v| Some(*v).filter(|v| v.is_finite())))
+        } else {
+            values.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(Vec::<Option<f64>>::deserialize(deserializer)?.into_iter().map(|v| v.unwrap_or(f64::NAN)).collect())
+        } else {
+            Vec::<f64>::deserialize(deserializer)
+        }
+    }
+}
+
+pub mod nan_map_as_null {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(values: &BTreeMap<String, f64>, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_map(values.iter().map(|(k, v)| (k, Some(*v).filter(|v| v.is_finite()))))
+        } else {
+            values.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<String, f64>, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(BTreeMap::<String, Option<f64>>::deserialize(deserializer)?
+                .into_iter()
+                .map(|(k, v)| (k, v.unwrap_or(f64::NAN)))
+                .collect())
+        } else {
+            BTreeMap::<String, f64>::deserialize(deserializer)
+        }
+    }
+}
+
+// adds to_bytes/from_bytes/to_json/from_json and pickle/copy support to pyclasses deriving Serialize and Deserialize
+// the class must carry #[pyclass(module = ...)] and the module must be in sys.modules, so pickle can find from_bytes
+macro_rules! impl_snapshot {
+    ($($name:ty),+ $(,)?) => {
+        $(
+            #[pyo3::pymethods]
+            impl $name {
+                pub fn to_bytes<'py>(&self, py: pyo3::Python<'py>) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyBytes>> {
+                    Ok(pyo3::types::PyBytes::new(py, &$crate::snapshot::to_bytes(self)?))
+                }
+
+                #[staticmethod]
+                pub fn from_bytes(bytes: &[u8]) -> $crate::Result<Self> {
+                    $crate::snapshot::from_bytes(bytes)
+                }
+
+                pub fn to_json(&self) -> $crate::Result<String> {
+                    $crate::snapshot::to_json(self)
+                }
+
+                #[staticmethod]
+                pub fn from_json(json: &str) -> $crate::Result<Self> {
+                    $crate::snapshot::from_json(json)
+                }
+
+                fn __getstate__<'py>(&self, py: pyo3::Python<'py>) -> pyo3::PyResult<pyo3::Bound<'py, pyo3::types::PyBytes>> {
+                    self.to_bytes(py)
+                }
+
+                fn __setstate__(&mut self, state: &[u8]) -> pyo3::PyResult<()> {
+                    *self = $crate::snapshot::from_bytes(state)?;
+                    Ok(())
+                }
+
+                fn __reduce__<'py>(
+                    slf: &pyo3::Bound<'py, Self>,
+                ) -> pyo3::PyResult<(pyo3::Bound<'py, pyo3::PyAny>, (pyo3::Bound<'py, pyo3::types::PyBytes>,))> {
+                    use pyo3::types::PyAnyMethods;
+                    let from_bytes = slf.as_any().get_type().getattr("from_bytes")?;
+                    Ok((from_bytes, (slf.borrow().to_bytes(slf.py())?,)))
+                }
+
+                fn __copy__(&self) -> $crate::Result<Self> {
+                    $crate::snapshot::from_bytes(&$crate::snapshot::to_bytes(self)?)
+                }
+
+                fn __deepcopy__(&self, _memo: &pyo3::Bound<'_, pyo3::PyAny>) -> $crate::Result<Self> {
+                    self.__copy__()
+                }
+            }
+        )+
+    };
+}
+pub(crate) use impl_snapshot;
@@ -1,3 +1,56 @@
+use crate::error::Result;
+
 pub trait QuoteHandler<T> {
-    fn on_quote(&mut self, quote: &T);
+    fn on_quote(&mut self, quote: &T) -> Result<()>;
 }
+
+// adds on_provisional/commit/rollback to strategies holding a `checkpoint: Option<Box<Self>>` field.
+// on_provisional applies an intraday bar after putting the committed state aside,
+// a later on_provisional or the final on_update replaces it, commit keeps it as final.
+// on_provisional and on_final are atomic: on error the strategy is left as it was before the call.
+macro_rules! impl_provisional {
+    ($name:ty, $quote:ty) => {
+        #[pyo3::pymethods]
+        impl $name {
+            pub fn on_provisional(&mut self, quote: &$quote) -> $crate::Result<()> {
+                let snapshot = self.clone();
+                self.rollback();
+                self.checkpoint = Some(Box::new(self.clone()));
+                let result = $crate::strategy::base::QuoteHandler::on_quote(self, quote);
+                if result.is_err() {
+                    *self = snapshot;
+                }
+                result
+            }
+
+            pub fn commit(&mut self) {
+                self.checkpoint = None;
+            }
+
+            pub fn rollback(&mut self) {
+                if let Some(committed) = self.checkpoint.take() {
+                    *self = *committed;
+                }
+            }
+
+            #[getter]
+            pub fn is_provisional(&self) -> bool {
+                self.checkpoint.is_some()
+            }
+        }
+
+        impl $name {
+            // the final bar replaces a pending provisional one
+            fn on_final(&mut self, quote: &$quote) -> $crate::Result<()> {
+                let snapshot = self.clone();
+                self.rollback();
+                let result = $crate::strategy::base::QuoteHandler::on_quote(self, quote);
+                if result.is_err() {
+                    *self = snapshot;
+                }
+                result
+            }
+        }
+    };
+}
+pub(crate) use impl_provisional;
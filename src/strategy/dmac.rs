@@ -1,41 +1,53 @@
-use super::base::QuoteHandler;
+use super::base::{impl_provisional, QuoteHandler};
+use crate::broker::analyzer::CN_TRADING_DAYS;
 use crate::broker::etf::EtfBroker;
 use crate::datatype::quote::Bar;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use crate::ta::ma::MA;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // Dual Moving Average Crossover Strategy
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DMAStrategy {
     #[pyo3(get)]
     broker: EtfBroker,
     fast_ma: MA,
     slow_ma: MA,
     entry_size: f64,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<Bar> for DMAStrategy {
-    fn on_quote(&mut self, bar: &Bar) {
-        let vwap = bar.amount / bar.volume;
-        let sma5 = self.fast_ma.update(vwap);
-        let sma20 = self.slow_ma.update(vwap);
+    fn on_quote(&mut self, bar: &Bar) -> Result<()> {
+        let Some(vwap) = bar.vwap() else {
+            // a bar without trades has no fill price, only mark the portfolio
+            self.broker.update_portfolio_value(bar);
+            return Ok(());
+        };
+        let sma5 = self.fast_ma.update_with_volume(vwap, bar.volume);
+        let sma20 = self.slow_ma.update_with_volume(vwap, bar.volume);
 
         if self.broker.active_position_len() < 5 {
             // buy
             if sma5 > sma20 {
-                self.broker.entry(bar, vwap, self.entry_size, None, None);
+                self.broker.entry(bar, vwap, self.entry_size, None, None)?;
             }
         } else if self.broker.active_position_len() > 0 {
             // sell
             if sma5 < sma20 {
                 if let Some(pos) = self.broker.active_position_first() {
-                    self.broker.exit(bar, vec![pos.id], vwap);
+                    self.broker.exit(bar, vec![pos.id], vwap)?;
                 }
             }
         }
 
         self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
 
@@ -43,18 +55,23 @@ impl QuoteHandler<Bar> for DMAStrategy {
 impl DMAStrategy {
     #[new]
     #[pyo3(signature = (init_cash=5e5, fast_period=5, slow_period=20, ma_type="sma", max_active_pos_len=6))]
-    pub fn new(init_cash: f64, fast_period: usize, slow_period: usize, ma_type: &str, max_active_pos_len: usize) -> Self {
+    pub fn new(init_cash: f64, fast_period: usize, slow_period: usize, ma_type: &str, max_active_pos_len: usize) -> Result<Self> {
         let original_size = (init_cash / max_active_pos_len as f64 / 100.0).floor() * 100.0;
-        Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
-            fast_ma: MA::new(fast_period, ma_type),
-            slow_ma: MA::new(slow_period, ma_type),
+        Ok(Self {
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, CN_TRADING_DAYS),
+            fast_ma: MA::new(fast_period, ma_type)?,
+            slow_ma: MA::new(slow_period, ma_type)?,
             entry_size: original_size,
-        }
+            checkpoint: None,
+        })
     }
 
-    pub fn on_update(&mut self, bar: &Bar) {
-        self.on_quote(bar);
+    pub fn on_update(&mut self, bar: &Bar) -> Result<()> {
+        self.on_final(bar)?;
         println!("portfolio={} at {:?}", self.broker.portfolio_value, bar);
+        Ok(())
     }
 }
+
+impl_snapshot!(DMAStrategy);
+impl_provisional!(DMAStrategy, Bar);
@@ -1,12 +1,17 @@
-use super::base::QuoteHandler;
+use super::base::{impl_provisional, QuoteHandler};
+use crate::broker::analyzer::CN_TRADING_DAYS;
 use crate::broker::etf::EtfBroker;
 use crate::datatype::quote::Bar;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use crate::ta::cross::Crosser;
 use crate::ta::ma::{EMA, MA};
 use crate::ta::volatility::ATR;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GridPercent {
     #[pyo3(get)]
     broker: EtfBroker,
@@ -19,15 +24,23 @@ pub struct GridPercent {
     long_croxes: Vec<Crosser>,
     short_croxes: Vec<Crosser>,
     ids: Vec<Option<u32>>,
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
     entry_zones: Vec<f64>,
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
     exit_zones: Vec<f64>,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<Bar> for GridPercent {
-    fn on_quote(&mut self, bar: &Bar) {
+    fn on_quote(&mut self, bar: &Bar) -> Result<()> {
         let ohlc4 = (bar.open + bar.high + bar.low + bar.close) / 4.0;
-        let vwap = bar.amount / bar.volume;
-        let ma_center = self.base_ma.update(ohlc4);
+        let Some(vwap) = bar.vwap() else {
+            // a bar without trades has no fill price, only mark the portfolio
+            self.broker.update_portfolio_value(bar);
+            return Ok(());
+        };
+        let ma_center = self.base_ma.update_with_volume(ohlc4, bar.volume);
 
         let premium_zones: Vec<f64> = (0..8)
             .map(|i| self.premium_smooth_mas[i].update(ma_center * (1.0 + 0.01 * self.band_mult * (i as f64 + 1.0))))
@@ -41,32 +54,33 @@ impl QuoteHandler<Bar> for GridPercent {
             self.entry_zones[i] = discount_zones[7 - i];
         }
         self.entry_zones[8] = ma_center;
-        for i in 9..16 {
-            self.entry_zones[i] = premium_zones[i - 9];
-        }
+        self.entry_zones[9..16].copy_from_slice(&premium_zones[..7]);
         // exit zones
         for i in 0..=6 {
             self.exit_zones[i] = discount_zones[6 - i];
         }
         self.exit_zones[7] = ma_center;
-        for i in 8..16 {
-            self.exit_zones[i] = premium_zones[i - 8];
-        }
+        self.exit_zones[8..16].copy_from_slice(&premium_zones);
 
         // Accumulate exit postion ids
         // exit should before entry
         let mut positions_to_exit = Vec::new();
+        let mut slots_to_free = Vec::new();
         for i in 0..16 {
             if self.short_croxes[i].update(bar.high, self.exit_zones[i]) == 1 {
                 if let Some(pos_id) = self.ids[i] {
                     positions_to_exit.push(pos_id);
-                    self.available_pos_num += 1;
-                    self.ids[i] = None;
+                    slots_to_free.push(i);
                 }
             }
         }
         if !positions_to_exit.is_empty() {
-            self.broker.exit(bar, positions_to_exit, vwap);
+            self.broker.exit(bar, positions_to_exit, vwap)?;
+            // the slots are freed once the broker accepted the exit
+            for i in slots_to_free {
+                self.ids[i] = None;
+                self.available_pos_num += 1;
+            }
         }
 
         // if opened postions smaller than threshold, entry position; else no entry
@@ -81,14 +95,15 @@ impl QuoteHandler<Bar> for GridPercent {
                 }
             }
             if let Some(i) = deepest_entry_crossing {
-                let entry_size = (self.entry_amount * (2.0 as f64).powi(cross_num - 1) / vwap / 100.0).floor() * 100.0;
-                let pos_id = self.broker.entry(bar, vwap, entry_size, None, None);
+                let entry_size = (self.entry_amount * 2.0_f64.powi(cross_num - 1) / vwap / 100.0).floor() * 100.0;
+                let pos_id = self.broker.entry(bar, vwap, entry_size, None, None)?;
                 self.available_pos_num -= 1;
                 self.ids[i] = Some(pos_id);
             }
         }
 
         self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
 
@@ -96,31 +111,34 @@ impl QuoteHandler<Bar> for GridPercent {
 impl GridPercent {
     #[new]
     #[pyo3(signature = (init_cash=5e5, ma_period=21, ma_type="sma", max_active_pos_len=6, band_mult=0.02))]
-    pub fn new(init_cash: f64, ma_period: usize, ma_type: &str, max_active_pos_len: usize, band_mult: f64) -> Self {
+    pub fn new(init_cash: f64, ma_period: usize, ma_type: &str, max_active_pos_len: usize, band_mult: f64) -> Result<Self> {
         let origin_amount = init_cash / max_active_pos_len as f64;
-        Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
-            base_ma: MA::new(ma_period, ma_type),
+        Ok(Self {
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, CN_TRADING_DAYS),
+            base_ma: MA::new(ma_period, ma_type)?,
             available_pos_num: max_active_pos_len,
             band_mult,
             entry_amount: origin_amount,
-            premium_smooth_mas: (0..8).map(|_| EMA::new(5)).collect(),
-            discount_smooth_mas: (0..8).map(|_| EMA::new(5)).collect(),
-            long_croxes: (0..16).map(|_| Crosser::new()).collect(),
-            short_croxes: (0..16).map(|_| Crosser::new()).collect(),
+            premium_smooth_mas: (0..8).map(|_| EMA::new(5)).collect::<Result<_>>()?,
+            discount_smooth_mas: (0..8).map(|_| EMA::new(5)).collect::<Result<_>>()?,
+            long_croxes: (0..16).map(|_| Crosser::new()).collect::<Result<_>>()?,
+            short_croxes: (0..16).map(|_| Crosser::new()).collect::<Result<_>>()?,
             ids: vec![None; 16],
             entry_zones: vec![0.0; 16],
             exit_zones: vec![0.0; 16],
-        }
+            checkpoint: None,
+        })
     }
 
-    pub fn on_update(&mut self, bar: &Bar) {
-        self.on_quote(bar);
+    pub fn on_update(&mut self, bar: &Bar) -> Result<()> {
+        self.on_final(bar)?;
         // println!("portfolio={}, {:?}", self.broker.portfolio_value, bar);
+        Ok(())
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GridATR {
     #[pyo3(get)]
     broker: EtfBroker,
@@ -134,15 +152,23 @@ pub struct GridATR {
     long_croxes: Vec<Crosser>,
     short_croxes: Vec<Crosser>,
     ids: Vec<Option<u32>>,
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
     entry_zones: Vec<f64>,
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
     exit_zones: Vec<f64>,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<Bar> for GridATR {
-    fn on_quote(&mut self, bar: &Bar) {
+    fn on_quote(&mut self, bar: &Bar) -> Result<()> {
         let ohlc4 = (bar.open + bar.high + bar.low + bar.close) / 4.0;
-        let vwap = bar.amount / bar.volume;
-        let ma_center = self.base_ma.update(ohlc4);
+        let Some(vwap) = bar.vwap() else {
+            // a bar without trades has no fill price, only mark the portfolio
+            self.broker.update_portfolio_value(bar);
+            return Ok(());
+        };
+        let ma_center = self.base_ma.update_with_volume(ohlc4, bar.volume);
         let atr_val = self.atr.update(bar.high, bar.low, bar.preclose);
 
         let premium_zones: Vec<f64> = (0..8).map(|i| self.premium_smooth_mas[i].update(ma_center + self.band_mult * atr_val * (i as f64 + 1.0))).collect();
@@ -153,32 +179,33 @@ impl QuoteHandler<Bar> for GridATR {
             self.entry_zones[i] = discount_zones[7 - i];
         }
         self.entry_zones[8] = ma_center;
-        for i in 9..16 {
-            self.entry_zones[i] = premium_zones[i - 9];
-        }
+        self.entry_zones[9..16].copy_from_slice(&premium_zones[..7]);
         // exit zones
         for i in 0..=6 {
             self.exit_zones[i] = discount_zones[6 - i];
         }
         self.exit_zones[7] = ma_center;
-        for i in 8..16 {
-            self.exit_zones[i] = premium_zones[i - 8];
-        }
+        self.exit_zones[8..16].copy_from_slice(&premium_zones);
 
         // Accumulate exit postion ids
         // exit should before entry
         let mut positions_to_exit = Vec::new();
+        let mut slots_to_free = Vec::new();
         for i in 0..16 {
             if self.short_croxes[i].update(bar.high, self.exit_zones[i]) == 1 {
                 if let Some(pos_id) = self.ids[i] {
                     positions_to_exit.push(pos_id);
-                    self.available_pos_num += 1;
-                    self.ids[i] = None;
+                    slots_to_free.push(i);
                 }
             }
         }
         if !positions_to_exit.is_empty() {
-            self.broker.exit(bar, positions_to_exit, vwap);
+            self.broker.exit(bar, positions_to_exit, vwap)?;
+            // the slots are freed once the broker accepted the exit
+            for i in slots_to_free {
+                self.ids[i] = None;
+                self.available_pos_num += 1;
+            }
         }
 
         // if opened postions smaller than threshold, entry position; else no entry
@@ -192,13 +219,14 @@ impl QuoteHandler<Bar> for GridATR {
             }
             if let Some(i) = deepest_entry_crossing {
                 let entry_size = (self.entry_amount / vwap / 100.0).floor() * 100.0;
-                let pos_id = self.broker.entry(bar, vwap, entry_size, None, None);
+                let pos_id = self.broker.entry(bar, vwap, entry_size, None, None)?;
                 self.available_pos_num -= 1;
                 self.ids[i] = Some(pos_id);
             }
         }
 
         self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
 
@@ -206,27 +234,33 @@ impl QuoteHandler<Bar> for GridATR {
 impl GridATR {
     #[new]
     #[pyo3(signature = (init_cash=5e5, ma_period=21, ma_type="sma", atr_period=60, atr_ma_type="rma", max_active_pos_len=6, band_mult=0.02))]
-    pub fn new(init_cash: f64, ma_period: usize, ma_type: &str, atr_period: usize, atr_ma_type: &str, max_active_pos_len: usize, band_mult: f64) -> Self {
+    pub fn new(init_cash: f64, ma_period: usize, ma_type: &str, atr_period: usize, atr_ma_type: &str, max_active_pos_len: usize, band_mult: f64) -> Result<Self> {
         let origin_amount = init_cash / max_active_pos_len as f64;
-        Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
-            base_ma: MA::new(ma_period, ma_type),
-            atr: ATR::new(atr_period, atr_ma_type),
+        Ok(Self {
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, CN_TRADING_DAYS),
+            base_ma: MA::new(ma_period, ma_type)?,
+            atr: ATR::new(atr_period, atr_ma_type)?,
             band_mult,
             available_pos_num: max_active_pos_len,
             entry_amount: origin_amount,
-            premium_smooth_mas: (0..8).map(|_| EMA::new(5)).collect(),
-            discount_smooth_mas: (0..8).map(|_| EMA::new(5)).collect(),
-            long_croxes: (0..16).map(|_| Crosser::new()).collect(),
-            short_croxes: (0..16).map(|_| Crosser::new()).collect(),
+            premium_smooth_mas: (0..8).map(|_| EMA::new(5)).collect::<Result<_>>()?,
+            discount_smooth_mas: (0..8).map(|_| EMA::new(5)).collect::<Result<_>>()?,
+            long_croxes: (0..16).map(|_| Crosser::new()).collect::<Result<_>>()?,
+            short_croxes: (0..16).map(|_| Crosser::new()).collect::<Result<_>>()?,
             ids: vec![None; 16],
             entry_zones: vec![0.0; 16],
             exit_zones: vec![0.0; 16],
-        }
+            checkpoint: None,
+        })
     }
 
-    pub fn on_update(&mut self, bar: &Bar) {
-        self.on_quote(bar);
+    pub fn on_update(&mut self, bar: &Bar) -> Result<()> {
+        self.on_final(bar)?;
         // println!("portfolio={}, {:?}", self.broker.portfolio_value, bar);
+        Ok(())
     }
 }
+
+impl_snapshot!(GridPercent, GridATR);
+impl_provisional!(GridPercent, Bar);
+impl_provisional!(GridATR, Bar);
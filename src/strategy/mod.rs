@@ -13,5 +13,7 @@ pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
     strategy.add_class::<qdii::GridCCI>()?;
     strategy.add_class::<sav::SavStg>()?;
     strategy.add_class::<sav::SavStgD>()?;
+    // register as bktrader.strategy so that pickle can import the classes
+    parent_module.py().import("sys")?.getattr("modules")?.set_item("bktrader.strategy", &strategy)?;
     parent_module.add_submodule(&strategy)
 }
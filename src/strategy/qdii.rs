@@ -1,12 +1,17 @@
-use super::base::QuoteHandler;
+use super::base::{impl_provisional, QuoteHandler};
+use crate::broker::analyzer::CN_TRADING_DAYS;
 use crate::broker::etf::EtfBroker;
 use crate::datatype::quote::Bar;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use crate::ta::cumulative::CumQuantile;
 use crate::ta::momentum::CCI;
 use crate::ta::rolling::{Container, RollingRank};
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GridCCI {
     #[pyo3(get)]
     pub broker: EtfBroker,
@@ -21,12 +26,18 @@ pub struct GridCCI {
     available_pos_num: usize,
     profit_limit: f64,
     loss_limit: f64,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<Bar> for GridCCI {
-    fn on_quote(&mut self, bar: &Bar) {
+    fn on_quote(&mut self, bar: &Bar) -> Result<()> {
         // in real-time quote, amount & volume should be a predicted value by real-time amount & volume
-        let vwap = bar.amount / bar.volume;
+        let Some(vwap) = bar.vwap() else {
+            // a bar without trades has no fill price, only mark the portfolio
+            self.broker.update_portfolio_value(bar);
+            return Ok(());
+        };
         let cci_val = self.cci.update(bar.high, bar.low, vwap);
         let quantile_val = self.quantiler.update(cci_val);
         let cci_rank = self.ranker.update(cci_val);
@@ -38,18 +49,18 @@ impl QuoteHandler<Bar> for GridCCI {
             if let Some(take_profit) = pos.take_profit {
                 if profit > take_profit {
                     positions_to_exit.push(pos.id);
-                    self.available_pos_num += 1;
                 }
             }
             if let Some(stop_loss) = pos.stop_loss {
                 if profit < stop_loss {
                     positions_to_exit.push(pos.id);
-                    self.available_pos_num += 1;
                 }
             }
         }
         if !positions_to_exit.is_empty() {
-            self.broker.exit(bar, positions_to_exit, vwap);
+            let exit_num = positions_to_exit.len();
+            self.broker.exit(bar, positions_to_exit, vwap)?;
+            self.available_pos_num += exit_num;
         }
 
         if self.available_pos_num > 0 {
@@ -58,12 +69,13 @@ impl QuoteHandler<Bar> for GridCCI {
                 // let multiplier = 1.1_f64.powi((self.max_pos_num - self.available_pos_num) as i32);
                 // let entry_size = (self.entry_amount * multiplier / vwap / 100.0).floor() * 100.0;
                 let entry_size = (self.entry_amount / vwap / 100.0).floor() * 100.0;
-                self.broker.entry(bar, vwap, entry_size, Some(self.loss_limit), Some(self.profit_limit));
+                self.broker.entry(bar, vwap, entry_size, Some(self.loss_limit), Some(self.profit_limit))?;
                 self.available_pos_num -= 1;
             }
         }
 
         self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
 
@@ -82,26 +94,31 @@ impl GridCCI {
         max_active_pos_len: usize,
         profit_limit: f64,
         loss_limit: f64,
-    ) -> Self {
+    ) -> Result<Self> {
         let origin_amount = init_cash / max_active_pos_len as f64;
-        Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
-            cci: CCI::new(cci_period, ma_type),
-            vol_differ: Container::new(2),
-            quantiler: CumQuantile::new(cum_quantile),
+        Ok(Self {
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, CN_TRADING_DAYS),
+            cci: CCI::new(cci_period, ma_type)?,
+            vol_differ: Container::new(2)?,
+            quantiler: CumQuantile::new(cum_quantile)?,
             cci_threshold,
-            ranker: RollingRank::new(rank_period),
+            ranker: RollingRank::new(rank_period)?,
             rank_limit,
             entry_amount: origin_amount,
             // max_pos_num: max_active_pos_len,
             available_pos_num: max_active_pos_len,
             profit_limit,
             loss_limit,
-        }
+            checkpoint: None,
+        })
     }
 
-    pub fn on_update(&mut self, bar: &Bar) {
-        self.on_quote(bar);
+    pub fn on_update(&mut self, bar: &Bar) -> Result<()> {
+        self.on_final(bar)?;
         // self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
+
+impl_snapshot!(GridCCI);
+impl_provisional!(GridCCI, Bar);
@@ -1,23 +1,30 @@
-use super::base::QuoteHandler;
+use super::base::{impl_provisional, QuoteHandler};
+use crate::broker::analyzer::{Analyzer, CN_SESSION_MINUTES, CN_TRADING_DAYS};
 use crate::broker::etf::EtfBroker;
 use crate::datatype::quote::{Bar, BarM};
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
 use crate::ta::rolling::Container;
 use crate::ta::savgol::Savgol;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
 #[allow(dead_code)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SavStg {
     #[pyo3(get)]
     pub broker: EtfBroker,
     entry_amount: f64,
     available_pos_num: usize,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<BarM> for SavStg {
-    fn on_quote(&mut self, _quote: &BarM) {
+    fn on_quote(&mut self, _quote: &BarM) -> Result<()> {
         // self.broker.update_portfolio_value(quote); # quote is not type Bar
-        todo!()
+        Err(Error::Unsupported("SavStg does not trade minute bars yet".into()))
     }
 }
 
@@ -28,18 +35,21 @@ impl SavStg {
     pub fn new(init_cash: f64, max_active_pos_len: usize) -> Self {
         let origin_amount = init_cash / max_active_pos_len as f64;
         Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
+            // minute bars
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, Analyzer::session_periods(CN_TRADING_DAYS, CN_SESSION_MINUTES, 1.0)),
             entry_amount: origin_amount,
             available_pos_num: max_active_pos_len,
+            checkpoint: None,
         }
     }
 
-    pub fn on_update(&mut self, quote: &BarM) {
-        self.on_quote(quote);
+    pub fn on_update(&mut self, quote: &BarM) -> Result<()> {
+        self.on_final(quote)
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.strategy")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SavStgD {
     #[pyo3(get)]
     pub broker: EtfBroker,
@@ -49,33 +59,40 @@ pub struct SavStgD {
     vd1_differ: Container,
     entry_amount: f64,
     available_pos_num: usize,
+    // committed state while a provisional bar is applied
+    checkpoint: Option<Box<Self>>,
 }
 
 impl QuoteHandler<Bar> for SavStgD {
-    fn on_quote(&mut self, bar: &Bar) {
-        let vwap = bar.amount / bar.volume;
-        let (pd1, pd2) = self.price_savgoler.update(vwap);
-        let (vd1, _vd2) = self.vol_savgoler.update(bar.volume);
+    fn on_quote(&mut self, bar: &Bar) -> Result<()> {
+        let Some(vwap) = bar.vwap() else {
+            // a bar without trades has no fill price, only mark the portfolio
+            self.broker.update_portfolio_value(bar);
+            return Ok(());
+        };
+        let price_derivs = self.price_savgoler.update(vwap);
+        let (pd1, pd2) = (price_derivs[1], price_derivs[2]);
+        let vd1 = self.vol_savgoler.update(bar.volume)[1];
         let (pd1_head, pd1_tail) = self.pd1_differ.update(pd1);
         let (_vd1_head, _vd1_tail) = self.vd1_differ.update(vd1);
 
         if (pd1_head > 0.0) && (pd1_tail <= 0.0) {
             let positions_to_exit: Vec<u32> = self.broker.active_positions().iter().map(|pos| pos.id).collect();
             if !positions_to_exit.is_empty() {
-                self.available_pos_num += positions_to_exit.len();
-                self.broker.exit(bar, positions_to_exit, vwap);
+                let exit_num = positions_to_exit.len();
+                self.broker.exit(bar, positions_to_exit, vwap)?;
+                self.available_pos_num += exit_num;
             }
         }
 
-        if self.available_pos_num > 0 {
-            if (pd1_head <= 0.0) && (pd1_tail > 0.0) && (pd2 > 0.0) {
-                let entry_size = (self.entry_amount / vwap / 100.0).floor() * 100.0;
-                self.broker.entry(bar, vwap, entry_size, None, None);
-                self.available_pos_num -= 1;
-            }
+        if self.available_pos_num > 0 && (pd1_head <= 0.0) && (pd1_tail > 0.0) && (pd2 > 0.0) {
+            let entry_size = (self.entry_amount / vwap / 100.0).floor() * 100.0;
+            self.broker.entry(bar, vwap, entry_size, None, None)?;
+            self.available_pos_num -= 1;
         }
 
         self.broker.update_portfolio_value(bar);
+        Ok(())
     }
 }
 
@@ -83,20 +100,25 @@ impl QuoteHandler<Bar> for SavStgD {
 impl SavStgD {
     #[new]
     #[pyo3(signature = (init_cash=5e5, price_win=20, vol_win=5, max_active_pos_len=6))]
-    pub fn new(init_cash: f64, price_win: usize, vol_win: usize, max_active_pos_len: usize) -> Self {
+    pub fn new(init_cash: f64, price_win: usize, vol_win: usize, max_active_pos_len: usize) -> Result<Self> {
         let origin_amount = init_cash / max_active_pos_len as f64;
-        Self {
-            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4),
-            price_savgoler: Savgol::new(price_win),
-            vol_savgoler: Savgol::new(vol_win),
-            pd1_differ: Container::new(2),
-            vd1_differ: Container::new(2),
+        Ok(Self {
+            broker: EtfBroker::new(init_cash, 5.0, 1.5e-4, CN_TRADING_DAYS),
+            price_savgoler: Savgol::new(price_win, 2, None, 1.0, None)?,
+            vol_savgoler: Savgol::new(vol_win, 2, None, 1.0, None)?,
+            pd1_differ: Container::new(2)?,
+            vd1_differ: Container::new(2)?,
             entry_amount: origin_amount,
             available_pos_num: max_active_pos_len,
-        }
+            checkpoint: None,
+        })
     }
 
-    pub fn on_update(&mut self, quote: &Bar) {
-        self.on_quote(quote);
+    pub fn on_update(&mut self, quote: &Bar) -> Result<()> {
+        self.on_final(quote)
     }
 }
+
+impl_snapshot!(SavStg, SavStgD);
+impl_provisional!(SavStg, BarM);
+impl_provisional!(SavStgD, Bar);
@@ -0,0 +1,265 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::{
+    ma::MA,
+    rolling::{RollingMax, RollingMin, RollingStdev},
+    volatility::ATR,
+};
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// every band returns (upper, middle, lower, %B, bandwidth)
+// %B is the position of price inside the band (0 at lower, 1 at upper), 0.5 for a flat band
+// bandwidth is the band width relative to the middle line
+fn band_output(upper: f64, middle: f64, lower: f64, price: f64) -> (f64, f64, f64, f64, f64) {
+    let width = upper - lower;
+    let percent_b = if width == 0.0 { 0.5 } else { (price - lower) / width };
+    (upper, middle, lower, percent_b, width / middle)
+}
+
+// Bollinger - middle ma ± mult population standard deviations of price over period
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bollinger {
+    ma: MA,
+    stdev: RollingStdev,
+    mult: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    price: f64,
+}
+
+#[pymethods]
+impl Bollinger {
+    #[new]
+    #[pyo3(signature = (period=20, mult=2.0, ma_type="sma"))]
+    pub fn new(period: usize, mult: f64, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            ma: MA::new(period, ma_type)?,
+            stdev: RollingStdev::new(period, 0)?,
+            mult,
+            price: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64, f64, f64) {
+        self.ma.update(new_val);
+        self.stdev.update(new_val);
+        self.price = new_val;
+        self.value()
+    }
+}
+
+// Keltner - middle ma of close ± mult ATR
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Keltner {
+    ma: MA,
+    atr: ATR,
+    mult: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    close: f64,
+}
+
+#[pymethods]
+impl Keltner {
+    #[new]
+    #[pyo3(signature = (ma_period=20, atr_period=10, mult=2.0, ma_type="ema", atr_ma_type="rma"))]
+    pub fn new(ma_period: usize, atr_period: usize, mult: f64, ma_type: &str, atr_ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            ma: MA::new(ma_period, ma_type)?,
+            atr: ATR::new(atr_period, atr_ma_type)?,
+            mult,
+            close: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, preclose: f64) -> (f64, f64, f64, f64, f64) {
+        self.ma.update(close);
+        self.atr.update(high, low, preclose);
+        self.close = close;
+        self.value()
+    }
+}
+
+// Donchian - highest high and lowest low over period, the middle is their midpoint
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Donchian {
+    low_miner: RollingMin,
+    high_maxer: RollingMax,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    close: f64,
+}
+
+#[pymethods]
+impl Donchian {
+    #[new]
+    #[pyo3(signature = (period=20))]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            low_miner: RollingMin::new(period)?,
+            high_maxer: RollingMax::new(period)?,
+            close: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> (f64, f64, f64, f64, f64) {
+        self.low_miner.update(low);
+        self.high_maxer.update(high);
+        self.close = close;
+        self.value()
+    }
+}
+
+// Envelope - middle ma ± percent of itself
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    ma: MA,
+    percent: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    price: f64,
+}
+
+#[pymethods]
+impl Envelope {
+    #[new]
+    #[pyo3(signature = (period=20, percent=2.5, ma_type="sma"))]
+    pub fn new(period: usize, percent: f64, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            ma: MA::new(period, ma_type)?,
+            percent,
+            price: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64, f64, f64) {
+        self.ma.update(new_val);
+        self.price = new_val;
+        self.value()
+    }
+}
+
+impl Indicator for Bollinger {
+    type Input = f64;
+    type Output = (f64, f64, f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64, f64, f64) {
+        let middle = self.ma.value();
+        let offset = self.mult * self.stdev.value();
+        band_output(middle + offset, middle, middle - offset, self.price)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ma.is_ready() && self.stdev.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.ma.warmup_period().max(self.stdev.warmup_period())
+    }
+
+    fn reset(&mut self) {
+        self.ma.reset();
+        self.stdev.reset();
+        self.price = f64::NAN;
+    }
+}
+
+impl Indicator for Keltner {
+    type Input = (f64, f64, f64, f64);
+    type Output = (f64, f64, f64, f64, f64);
+
+    fn update(&mut self, (high, low, close, preclose): (f64, f64, f64, f64)) -> (f64, f64, f64, f64, f64) {
+        Self::update(self, high, low, close, preclose)
+    }
+
+    fn value(&self) -> (f64, f64, f64, f64, f64) {
+        let middle = self.ma.value();
+        let offset = self.mult * self.atr.value();
+        band_output(middle + offset, middle, middle - offset, self.close)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ma.is_ready() && self.atr.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.ma.warmup_period().max(self.atr.warmup_period())
+    }
+
+    fn reset(&mut self) {
+        self.ma.reset();
+        self.atr.reset();
+        self.close = f64::NAN;
+    }
+}
+
+impl Indicator for Donchian {
+    type Input = (f64, f64, f64);
+    type Output = (f64, f64, f64, f64, f64);
+
+    fn update(&mut self, (high, low, close): (f64, f64, f64)) -> (f64, f64, f64, f64, f64) {
+        Self::update(self, high, low, close)
+    }
+
+    fn value(&self) -> (f64, f64, f64, f64, f64) {
+        let upper = self.high_maxer.value();
+        let lower = self.low_miner.value();
+        band_output(upper, (upper + lower) / 2.0, lower, self.close)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.low_miner.is_ready() && self.high_maxer.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.high_maxer.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.low_miner.reset();
+        self.high_maxer.reset();
+        self.close = f64::NAN;
+    }
+}
+
+impl Indicator for Envelope {
+    type Input = f64;
+    type Output = (f64, f64, f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64, f64, f64) {
+        let middle = self.ma.value();
+        let offset = middle * self.percent / 100.0;
+        band_output(middle + offset, middle, middle - offset, self.price)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ma.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.ma.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.ma.reset();
+        self.price = f64::NAN;
+    }
+}
+
+impl_snapshot!(Bollinger, Keltner, Donchian, Envelope);
+impl_batch!(Bollinger, (new_val) -> (0: f64, 1: f64, 2: f64, 3: f64, 4: f64));
+impl_batch!(Keltner, (high, low, close, preclose) -> (0: f64, 1: f64, 2: f64, 3: f64, 4: f64));
+impl_batch!(Donchian, (high, low, close) -> (0: f64, 1: f64, 2: f64, 3: f64, 4: f64));
+impl_batch!(Envelope, (new_val) -> (0: f64, 1: f64, 2: f64, 3: f64, 4: f64));
+impl_indicator!(Bollinger, Keltner, Donchian, Envelope);
@@ -0,0 +1,55 @@
+use numpy::ndarray::ArrayView1;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// all input columns of a batch must be aligned
+pub fn check_len(columns: &[&ArrayView1<'_, f64>]) -> PyResult<usize> {
+    let n = columns[0].len();
+    if columns.iter().any(|column| column.len() != n) {
+        return Err(PyValueError::new_err("input arrays must have the same length"));
+    }
+    Ok(n)
+}
+
+// adds batch() to an indicator: numpy arrays in (read without copying), numpy arrays out.
+// each row goes through Indicator::update, so the result is identical to calling update() on each element in order,
+// and the state carries over, so batch() and update() can be mixed, e.g. warm up with history then stream realtime quotes.
+// single output: impl_batch!(SMA, (value) -> f64)
+// multiple outputs, named by tuple index: impl_batch!(KDJ, (price, high, low) -> (0: f64, 1: f64, 2: f64))
+macro_rules! impl_batch {
+    ($name:ty, ($($arg:ident),+) -> ($($idx:tt: $out:ty),+)) => {
+        #[pyo3::pymethods]
+        impl $name {
+            pub fn batch<'py>(
+                &mut self,
+                py: pyo3::Python<'py>,
+                $($arg: numpy::PyReadonlyArray1<'py, f64>),+
+            ) -> pyo3::PyResult<($(pyo3::Bound<'py, numpy::PyArray1<$out>>),+)> {
+                $(let $arg = $arg.as_array();)+
+                let n = $crate::ta::batch::check_len(&[$(&$arg),+])?;
+                let mut columns = ($(Vec::<$out>::with_capacity(n)),+);
+                for i in 0..n {
+                    let values = $crate::ta::indicator::Indicator::update(self, ($($arg[i]),+));
+                    $(columns.$idx.push(values.$idx);)+
+                }
+                Ok(($(numpy::PyArray1::from_vec(py, columns.$idx)),+))
+            }
+        }
+    };
+    ($name:ty, ($($arg:ident),+) -> $out:ty) => {
+        #[pyo3::pymethods]
+        impl $name {
+            pub fn batch<'py>(
+                &mut self,
+                py: pyo3::Python<'py>,
+                $($arg: numpy::PyReadonlyArray1<'py, f64>),+
+            ) -> pyo3::PyResult<pyo3::Bound<'py, numpy::PyArray1<$out>>> {
+                $(let $arg = $arg.as_array();)+
+                let n = $crate::ta::batch::check_len(&[$(&$arg),+])?;
+                let column: Vec<$out> = (0..n).map(|i| $crate::ta::indicator::Indicator::update(self, ($($arg[i]),+))).collect();
+                Ok(numpy::PyArray1::from_vec(py, column))
+            }
+        }
+    };
+}
+pub(crate) use impl_batch;
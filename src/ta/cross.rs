@@ -1,7 +1,13 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
 use super::rolling::Container;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Crosser {
     pub x_container: Container,
     pub y_container: Container,
@@ -10,16 +16,31 @@ pub struct Crosser {
 #[pymethods]
 impl Crosser {
     #[new]
-    pub fn new() -> Self {
-        Self {
-            x_container: Container::new(2),
-            y_container: Container::new(2),
-        }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            x_container: Container::new(2)?,
+            y_container: Container::new(2)?,
+        })
     }
 
     pub fn update(&mut self, x: f64, y: f64) -> i8 {
-        let (x_head, x_tail) = self.x_container.update(x);
-        let (y_head, y_tail) = self.y_container.update(y);
+        self.x_container.update(x);
+        self.y_container.update(y);
+        self.value()
+    }
+}
+
+impl Indicator for Crosser {
+    type Input = (f64, f64);
+    type Output = i8;
+
+    fn update(&mut self, (x, y): (f64, f64)) -> i8 {
+        Self::update(self, x, y)
+    }
+
+    fn value(&self) -> i8 {
+        let (x_head, x_tail) = (self.x_container.head(), self.x_container.tail());
+        let (y_head, y_tail) = (self.y_container.head(), self.y_container.tail());
         if (x_head > y_head) && (x_tail < y_tail) {
             // x cross down y
             -1
@@ -30,4 +51,21 @@ impl Crosser {
             0
         }
     }
+
+    fn is_ready(&self) -> bool {
+        self.x_container.is_valid() && self.y_container.is_valid()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.x_container.len()
+    }
+
+    fn reset(&mut self) {
+        self.x_container.reset();
+        self.y_container.reset();
+    }
 }
+
+impl_snapshot!(Crosser);
+impl_batch!(Crosser, (x, y) -> i8);
+impl_indicator!(Crosser);
@@ -1,7 +1,15 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::rolling::check_quantile;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumMax {
+    #[serde(with = "crate::snapshot::nan_as_null")]
     max: f64,
 }
 
@@ -18,8 +26,10 @@ impl CumMax {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumMin {
+    #[serde(with = "crate::snapshot::nan_as_null")]
     min: f64,
 }
 
@@ -36,10 +46,18 @@ impl CumMin {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumMinMax {
+    #[serde(with = "crate::snapshot::nan_as_null")]
     min: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
     max: f64,
+    // kept for reset
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    init_min: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    init_max: f64,
 }
 
 #[pymethods]
@@ -49,6 +67,8 @@ impl CumMinMax {
         Self {
             min: init_min, // f64::INFINITY
             max: init_max, // f64::NEG_INFINITY,
+            init_min,
+            init_max,
         }
     }
 
@@ -59,7 +79,8 @@ impl CumMinMax {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumMean {
     count: usize,
     sum: f64,
@@ -78,11 +99,12 @@ impl CumMean {
             self.sum += new_val;
         }
 
-        self.sum / self.count as f64
+        self.value()
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumMedian {
     values: Vec<f64>,
 }
@@ -108,7 +130,7 @@ impl CumMedian {
             return f64::NAN;
         }
 
-        if length % 2 == 0 {
+        if length.is_multiple_of(2) {
             let mid = length / 2;
             (self.values[mid - 1] + self.values[mid]) / 2.0
         } else {
@@ -117,7 +139,8 @@ impl CumMedian {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CumQuantile {
     quantile: f64,
     dataset: Vec<f64>,
@@ -126,11 +149,12 @@ pub struct CumQuantile {
 #[pymethods]
 impl CumQuantile {
     #[new]
-    pub fn new(quantile: f64) -> Self {
-        Self {
+    pub fn new(quantile: f64) -> Result<Self> {
+        check_quantile(quantile)?;
+        Ok(Self {
             quantile,
             dataset: Vec::with_capacity(512),
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -157,3 +181,165 @@ impl CumQuantile {
         lower_value + fraction * (upper_value - lower_value)
     }
 }
+
+impl Indicator for CumMax {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.max
+    }
+
+    fn is_ready(&self) -> bool {
+        self.max > f64::NEG_INFINITY
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.max = f64::NEG_INFINITY;
+    }
+}
+
+impl Indicator for CumMin {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.min
+    }
+
+    fn is_ready(&self) -> bool {
+        self.min < f64::INFINITY
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.min = f64::INFINITY;
+    }
+}
+
+impl Indicator for CumMinMax {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        (self.min, self.max)
+    }
+
+    // a finite seed is ready at once
+    fn is_ready(&self) -> bool {
+        self.min <= self.max
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.min = self.init_min;
+        self.max = self.init_max;
+    }
+}
+
+impl Indicator for CumMean {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+
+    fn is_ready(&self) -> bool {
+        self.count > 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0.0;
+    }
+}
+
+impl Indicator for CumMedian {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.median()
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.values.is_empty()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+    }
+}
+
+impl Indicator for CumQuantile {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.quantile()
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.dataset.is_empty()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.dataset.clear();
+    }
+}
+
+impl_snapshot!(CumMax, CumMin, CumMinMax, CumMean, CumMedian, CumQuantile);
+impl_batch!(CumMax, (new_val) -> f64);
+impl_batch!(CumMin, (new_val) -> f64);
+impl_batch!(CumMinMax, (new_val) -> (0: f64, 1: f64));
+impl_batch!(CumMean, (new_val) -> f64);
+impl_batch!(CumMedian, (new_val) -> f64);
+impl_batch!(CumQuantile, (new_val) -> f64);
+impl_indicator!(CumMax, CumMin, CumMinMax, CumMean, CumMedian, CumQuantile);
@@ -1,134 +1,1099 @@
-use super::{ma::WMA, rolling::Container};
-use core::f64;
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::rolling::Container;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-// HtPhasor - Hilbert Transform - Phasor Components
-// approximation
-#[pyclass]
+// Hilbert transform indicators ported from TA-Lib (ta_HT_*.c, ta_MAMA.c): same warmup, the odd/even bar
+// transforms and the period smoothing. the tests below hold reference vectors for the dominant cycle outputs.
+// a non-finite price is skipped: it returns NaN and leaves the state untouched.
+
+const HT_A: f64 = 0.0962;
+const HT_B: f64 = 0.5769;
+// TA-Lib keeps up to 50 smoothed prices for the dominant cycle phase and 50 prices for the trendline
+const HT_BUFFER_SIZE: usize = 50;
+
+// DO_HILBERT_TRANSFORM, separate state for odd and even bars
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct HilbertTransform {
+    odd: [f64; 3],
+    even: [f64; 3],
+    value: f64,
+    prev_odd: f64,
+    prev_even: f64,
+    prev_input_odd: f64,
+    prev_input_even: f64,
+}
+
+impl HilbertTransform {
+    fn update(&mut self, input: f64, idx: usize, adjusted_prev_period: f64, even: bool) -> f64 {
+        let (buf, prev, prev_input) = if even {
+            (&mut self.even, &mut self.prev_even, &mut self.prev_input_even)
+        } else {
+            (&mut self.odd, &mut self.prev_odd, &mut self.prev_input_odd)
+        };
+        let temp = HT_A * input;
+        let mut value = -buf[idx];
+        buf[idx] = temp;
+        value += temp;
+        value -= *prev;
+        *prev = HT_B * *prev_input;
+        value += *prev;
+        *prev_input = input;
+        self.value = value * adjusted_prev_period;
+        self.value
+    }
+}
+
+// shared part of every Hilbert indicator: the 4 bar price wma, the transforms and the smoothed period
+#[derive(Clone, Serialize, Deserialize)]
+struct HilbertCore {
+    // price wma bars run before the transforms start, 9 or 34 in TA-Lib
+    wma_warmup: usize,
+    // TA-Lib lookback, the first output is at bar index lookback
+    lookback: usize,
+    bars: usize,
+    prices: Container,
+    smooth_prices: Container,
+    wma_sub: f64,
+    wma_sum: f64,
+    wma_trailing: f64,
+    hilbert_idx: usize,
+    detrender: HilbertTransform,
+    q1: HilbertTransform,
+    ji: HilbertTransform,
+    jq: HilbertTransform,
+    i1_odd_prev2: f64,
+    i1_odd_prev3: f64,
+    i1_even_prev2: f64,
+    i1_even_prev3: f64,
+    // detrender delayed by 3 bars, the in-phase component of the current bar
+    i1: f64,
+    prev_i2: f64,
+    prev_q2: f64,
+    re: f64,
+    im: f64,
+    period: f64,
+    smooth_period: f64,
+}
+
+impl HilbertCore {
+    // TA-Lib zero-fills the smoothed prices, so the early DFT sums of the phase stay finite
+    fn new(wma_warmup: usize, lookback: usize) -> Result<Self> {
+        Ok(Self::with_buffers(wma_warmup, lookback, Container::new(HT_BUFFER_SIZE)?, Container::filled(HT_BUFFER_SIZE, 0.0)?))
+    }
+
+    fn with_buffers(wma_warmup: usize, lookback: usize, prices: Container, smooth_prices: Container) -> Self {
+        Self {
+            wma_warmup,
+            lookback,
+            bars: 0,
+            prices,
+            smooth_prices,
+            wma_sub: 0.0,
+            wma_sum: 0.0,
+            wma_trailing: 0.0,
+            hilbert_idx: 0,
+            detrender: HilbertTransform::default(),
+            q1: HilbertTransform::default(),
+            ji: HilbertTransform::default(),
+            jq: HilbertTransform::default(),
+            i1_odd_prev2: 0.0,
+            i1_odd_prev3: 0.0,
+            i1_even_prev2: 0.0,
+            i1_even_prev3: 0.0,
+            i1: 0.0,
+            prev_i2: 0.0,
+            prev_q2: 0.0,
+            re: 0.0,
+            im: 0.0,
+            period: 0.0,
+            smooth_period: 0.0,
+        }
+    }
+
+    // DO_PRICE_WMA, running sums of the weights 4, 3, 2, 1
+    fn price_wma(&mut self, price: f64) -> f64 {
+        self.wma_sub += price - self.wma_trailing;
+        self.wma_sum += price * 4.0;
+        // the price 3 bars ago leaves the window next bar
+        self.wma_trailing = self.prices.get(HT_BUFFER_SIZE - 4);
+        let smoothed = self.wma_sum * 0.1;
+        self.wma_sum -= self.wma_sub;
+        smoothed
+    }
+
+    // returns true once the transforms ran for this bar
+    fn update(&mut self, price: f64) -> bool {
+        let today = self.bars;
+        self.bars += 1;
+        self.prices.update(price);
+        if today < 3 {
+            self.wma_sub += price;
+            self.wma_sum += price * (today + 1) as f64;
+            return false;
+        }
+        if today < 3 + self.wma_warmup {
+            self.price_wma(price);
+            return false;
+        }
+
+        let adjusted_prev_period = 0.075 * self.period + 0.54;
+        let smoothed = self.price_wma(price);
+        self.smooth_prices.update(smoothed);
+
+        let idx = self.hilbert_idx;
+        let even = today.is_multiple_of(2);
+        let i1 = if even { self.i1_even_prev3 } else { self.i1_odd_prev3 };
+        let detrender = self.detrender.update(smoothed, idx, adjusted_prev_period, even);
+        let q1 = self.q1.update(detrender, idx, adjusted_prev_period, even);
+        let ji = self.ji.update(i1, idx, adjusted_prev_period, even);
+        let jq = self.jq.update(q1, idx, adjusted_prev_period, even);
+        if even {
+            self.hilbert_idx = (self.hilbert_idx + 1) % 3;
+            self.i1_odd_prev3 = self.i1_odd_prev2;
+            self.i1_odd_prev2 = detrender;
+        } else {
+            self.i1_even_prev3 = self.i1_even_prev2;
+            self.i1_even_prev2 = detrender;
+        }
+        self.i1 = i1;
+        let q2 = 0.2 * (q1 + ji) + 0.8 * self.prev_q2;
+        let i2 = 0.2 * (i1 - jq) + 0.8 * self.prev_i2;
+
+        // homodyne discriminator, the period is limited to 0.67..1.5 times the previous one and 6..50 bars
+        self.re = 0.2 * (i2 * self.prev_i2 + q2 * self.prev_q2) + 0.8 * self.re;
+        self.im = 0.2 * (i2 * self.prev_q2 - q2 * self.prev_i2) + 0.8 * self.im;
+        self.prev_q2 = q2;
+        self.prev_i2 = i2;
+        let prev_period = self.period;
+        if self.im != 0.0 && self.re != 0.0 {
+            self.period = 360.0 / (self.im / self.re).atan().to_degrees();
+        }
+        self.period = self.period.min(1.5 * prev_period).max(0.67 * prev_period).clamp(6.0, 50.0);
+        self.period = 0.2 * self.period + 0.8 * prev_period;
+        self.smooth_period = 0.33 * self.period + 0.67 * self.smooth_period;
+        true
+    }
+
+    // quadrature component of the current bar
+    fn q1(&self) -> f64 {
+        self.q1.value
+    }
+
+    fn is_ready(&self) -> bool {
+        self.bars > self.lookback
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.lookback + 1
+    }
+
+    // reuses the buffers, their length was checked by new()
+    fn reset(&mut self) {
+        let (mut prices, mut smooth_prices) = (self.prices.clone(), self.smooth_prices.clone());
+        prices.reset();
+        smooth_prices.reset_with(0.0);
+        *self = Self::with_buffers(self.wma_warmup, self.lookback, prices, smooth_prices);
+    }
+}
+
+// dominant cycle phase in degrees from a DFT of the smoothed prices over the smoothed period
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct DCPhase {
+    phase: f64,
+}
+
+impl DCPhase {
+    fn update(&mut self, core: &HilbertCore) -> f64 {
+        let dc_period = (core.smooth_period + 0.5) as usize;
+        let mut real_part = 0.0;
+        let mut imag_part = 0.0;
+        for i in 0..dc_period {
+            let angle = i as f64 * 2.0 * std::f64::consts::PI / dc_period as f64;
+            let smoothed = core.smooth_prices.get(HT_BUFFER_SIZE - 1 - i);
+            real_part += angle.sin() * smoothed;
+            imag_part += angle.cos() * smoothed;
+        }
+
+        if imag_part.abs() > 0.0 {
+            self.phase = (real_part / imag_part).atan().to_degrees();
+        } else if real_part < 0.0 {
+            self.phase -= 90.0;
+        } else if real_part > 0.0 {
+            self.phase += 90.0;
+        }
+        self.phase += 90.0;
+        // compensate the one bar lag of the wma
+        self.phase += 360.0 / core.smooth_period;
+        if imag_part < 0.0 {
+            self.phase += 180.0;
+        }
+        if self.phase > 315.0 {
+            self.phase -= 360.0;
+        }
+        self.phase
+    }
+}
+
+fn sine_output(phase: f64) -> (f64, f64) {
+    (phase.to_radians().sin(), (phase + 45.0).to_radians().sin())
+}
+
+// TA-Lib trendline: simple average of the prices over the dominant cycle, smoothed by a 4 bar wma
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Trendline {
+    i_trend1: f64,
+    i_trend2: f64,
+    i_trend3: f64,
+}
+
+impl Trendline {
+    fn update(&mut self, core: &HilbertCore) -> f64 {
+        let dc_period = (core.smooth_period + 0.5) as usize;
+        let mut average = (0..dc_period).map(|i| core.prices.get(HT_BUFFER_SIZE - 1 - i)).sum::<f64>();
+        if dc_period > 0 {
+            average /= dc_period as f64;
+        }
+        let trendline = (4.0 * average + 3.0 * self.i_trend1 + 2.0 * self.i_trend2 + self.i_trend3) / 10.0;
+        self.i_trend3 = self.i_trend2;
+        self.i_trend2 = self.i_trend1;
+        self.i_trend1 = average;
+        trendline
+    }
+}
+
+// HtPhasor - Hilbert Transform - Phasor Components, returns (inphase, quadrature)
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HtPhasor {
-    container: Container,
-    wmaer: WMA,
-    coeff_a: f64,
-    coeff_b: f64,
+    core: HilbertCore,
 }
 
 #[pymethods]
 impl HtPhasor {
     #[new]
-    pub fn new() -> Self {
-        Self {
-            container: Container::new(10),
-            wmaer: WMA::new(4),
-            coeff_a: 0.0962,
-            coeff_b: 0.5769,
-        }
+    pub fn new() -> Result<Self> {
+        Ok(Self { core: HilbertCore::new(9, 32)? })
     }
 
     pub fn update(&mut self, new_val: f64) -> (f64, f64) {
-        let smooth_price = self.wmaer.update(new_val);
-        self.container.update(smooth_price);
-        // Quadrature (Q): Uses the current Detrender value, i.e., Detrender[0].
-        let detrender0 = self.coeff_a * self.container.get(9) + self.coeff_b * self.container.get(7) - self.coeff_b * self.container.get(5) - self.coeff_a * self.container.get(3);
-        // let detrender1 = self.coeff_a * self.container.get(8) + self.coeff_b * self.container.get(6) - self.coeff_b * self.container.get(4) - self.coeff_a * self.container.get(2);
-        // let detrender2 = self.coeff_a * self.container.get(7) + self.coeff_b * self.container.get(5) - self.coeff_b * self.container.get(3) - self.coeff_a * self.container.get(1);
-        // InPhase (I): Uses the Detrender value lagged by 3 periods
-        let detrender3 = self.coeff_a * self.container.get(6) + self.coeff_b * self.container.get(4) - self.coeff_b * self.container.get(2) - self.coeff_a * self.container.get(0);
-
-        (detrender3, detrender0)
+        if !new_val.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        self.core.update(new_val);
+        self.value()
     }
 }
 
 // HtDCPeriod - Hilbert Transform - Dominant Cycle Period
-// approximation
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HtDCPeriod {
-    ht_phasor: HtPhasor,
-    container: Container,
+    core: HilbertCore,
 }
 
 #[pymethods]
 impl HtDCPeriod {
     #[new]
-    pub fn new() -> Self {
-        Self {
-            ht_phasor: HtPhasor::new(),
-            container: Container::new(2),
-        }
+    pub fn new() -> Result<Self> {
+        Ok(Self { core: HilbertCore::new(9, 32)? })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
-        let (q, i) = self.ht_phasor.update(new_val);
-        let degree = (q / i).atan();
-        let (head, tail) = self.container.update(degree);
-        2.0 * f64::consts::PI / (tail - head)
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        self.core.update(new_val);
+        self.value()
     }
 }
 
-// HtDCPhase - Hilbert Transform - Dominant Cycle Phase
-// approximation
-#[pyclass]
+// HtDCPhase - Hilbert Transform - Dominant Cycle Phase in degrees
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HtDCPhase {
-    ht_phasor: HtPhasor,
+    core: HilbertCore,
+    dc_phase: DCPhase,
 }
 
 #[pymethods]
 impl HtDCPhase {
     #[new]
-    pub fn new() -> Self {
-        Self { ht_phasor: HtPhasor::new() }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            core: HilbertCore::new(34, 63)?,
+            dc_phase: DCPhase::default(),
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
-        let (q, i) = self.ht_phasor.update(new_val);
-        (q / i).atan().to_degrees()
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        if self.core.update(new_val) {
+            self.dc_phase.update(&self.core);
+        }
+        self.value()
     }
 }
 
-// HtSine - Hilbert Transform - SineWave
-// approximation
-#[pyclass]
+// HtSine - Hilbert Transform - SineWave, returns (sine, leadsine) with the lead 45 degrees ahead
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HtSine {
-    ht_phasor: HtPhasor,
+    core: HilbertCore,
+    dc_phase: DCPhase,
 }
 
 #[pymethods]
 impl HtSine {
     #[new]
-    pub fn new() -> Self {
-        Self { ht_phasor: HtPhasor::new() }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            core: HilbertCore::new(34, 63)?,
+            dc_phase: DCPhase::default(),
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> (f64, f64) {
-        let (q, i) = self.ht_phasor.update(new_val);
-        let phi = (q / i).atan();
-        let sine_wave = phi.sin();
-        let lead_sine_wave = (phi + f64::consts::PI / 4.0).sin();
-        (sine_wave, lead_sine_wave)
+        if !new_val.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        if self.core.update(new_val) {
+            self.dc_phase.update(&self.core);
+        }
+        self.value()
+    }
+}
+
+// HtTrendline - Hilbert Transform - Instantaneous Trendline
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HtTrendline {
+    core: HilbertCore,
+    trendline: Trendline,
+    value: f64,
+}
+
+#[pymethods]
+impl HtTrendline {
+    #[new]
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            core: HilbertCore::new(34, 63)?,
+            trendline: Trendline::default(),
+            value: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        if self.core.update(new_val) {
+            self.value = self.trendline.update(&self.core);
+        }
+        self.value()
     }
 }
 
-// HtTrendMode - Hilbert Transform - Trend vs Cycle Mode
-// approximation
-#[pyclass]
+// HtTrendMode - Hilbert Transform - Trend vs Cycle Mode, 1 for trend and 0 for cycle
+// trend unless the sine lines crossed within half a cycle or the phase advances at the cycle rate,
+// a price 1.5% away from the trendline is always a trend
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HtTrendMode {
-    dc_period: HtDCPeriod,
-    container: Container,
+    core: HilbertCore,
+    dc_phase: DCPhase,
+    trendline: Trendline,
+    sine: f64,
+    lead_sine: f64,
+    days_in_trend: usize,
+    trend: f64,
 }
 
 #[pymethods]
 impl HtTrendMode {
     #[new]
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            core: HilbertCore::new(34, 63)?,
+            dc_phase: DCPhase::default(),
+            trendline: Trendline::default(),
+            sine: 0.0,
+            lead_sine: 0.0,
+            days_in_trend: 0,
+            trend: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        if self.core.update(new_val) {
+            self.update_trend();
+        }
+        self.value()
+    }
+}
+
+impl HtTrendMode {
+    fn update_trend(&mut self) {
+        let prev_phase = self.dc_phase.phase;
+        let phase = self.dc_phase.update(&self.core);
+        let (prev_sine, prev_lead_sine) = (self.sine, self.lead_sine);
+        (self.sine, self.lead_sine) = sine_output(phase);
+        let trendline = self.trendline.update(&self.core);
+
+        let mut trend = true;
+        // days in trend since the last crossing of the sine lines
+        if (self.sine > self.lead_sine && prev_sine <= prev_lead_sine) || (self.sine < self.lead_sine && prev_sine >= prev_lead_sine) {
+            self.days_in_trend = 0;
+            trend = false;
+        }
+        self.days_in_trend += 1;
+        let smooth_period = self.core.smooth_period;
+        if (self.days_in_trend as f64) < 0.5 * smooth_period {
+            trend = false;
+        }
+        let phase_change = phase - prev_phase;
+        if smooth_period != 0.0 && phase_change > 0.67 * 360.0 / smooth_period && phase_change < 1.5 * 360.0 / smooth_period {
+            trend = false;
+        }
+        let smoothed = self.core.smooth_prices.tail();
+        if trendline != 0.0 && ((smoothed - trendline) / trendline).abs() >= 0.015 {
+            trend = true;
+        }
+        self.trend = if trend { 1.0 } else { 0.0 };
+    }
+}
+
+// MAMA - MESA Adaptive Moving Average, returns (mama, fama)
+// the alpha is fast_limit over the phase change, at least slow_limit; FAMA follows MAMA with half the alpha
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MAMA {
+    core: HilbertCore,
+    fast_limit: f64,
+    slow_limit: f64,
+    prev_phase: f64,
+    mama: f64,
+    fama: f64,
+}
+
+#[pymethods]
+impl MAMA {
+    #[new]
+    #[pyo3(signature = (fast_limit=0.5, slow_limit=0.05))]
+    pub fn new(fast_limit: f64, slow_limit: f64) -> Result<Self> {
+        Ok(Self {
+            core: HilbertCore::new(9, 32)?,
+            fast_limit,
+            slow_limit,
+            prev_phase: 0.0,
+            mama: 0.0,
+            fama: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64) {
+        if !new_val.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        if self.core.update(new_val) {
+            let i1 = self.core.i1;
+            let phase = if i1 != 0.0 { (self.core.q1() / i1).atan().to_degrees() } else { 0.0 };
+            let delta_phase = (self.prev_phase - phase).max(1.0);
+            self.prev_phase = phase;
+            let alpha = if delta_phase > 1.0 {
+                (self.fast_limit / delta_phase).max(self.slow_limit)
+            } else {
+                self.fast_limit
+            };
+            self.mama = alpha * new_val + (1.0 - alpha) * self.mama;
+            self.fama = 0.5 * alpha * self.mama + (1.0 - 0.5 * alpha) * self.fama;
+        }
+        self.value()
+    }
+}
+
+// SuperSmoother - Ehlers' 2 pole Butterworth low pass filter with a cutoff period,
+// the first 2 bars pass the price through
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SuperSmoother {
+    c1: f64,
+    c2: f64,
+    c3: f64,
+    bars: usize,
+    prev_val: f64,
+    filt1: f64,
+    filt2: f64,
+}
+
+#[pymethods]
+impl SuperSmoother {
+    #[new]
+    #[pyo3(signature = (period=10))]
+    pub fn new(period: usize) -> Self {
+        let a1 = (-std::f64::consts::SQRT_2 * std::f64::consts::PI / period as f64).exp();
+        let c2 = 2.0 * a1 * (std::f64::consts::SQRT_2 * std::f64::consts::PI / period as f64).cos();
+        let c3 = -a1 * a1;
         Self {
-            dc_period: HtDCPeriod::new(),
-            container: Container::new(2),
+            c1: 1.0 - c2 - c3,
+            c2,
+            c3,
+            bars: 0,
+            prev_val: 0.0,
+            filt1: 0.0,
+            filt2: 0.0,
         }
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
-        let dc_period = self.dc_period.update(new_val);
-        let (head, tail) = self.container.update(dc_period);
-        let trend_mode = if tail > head { 1.0 } else { 0.0 };
-        trend_mode
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        let filt = if self.bars < 2 {
+            new_val
+        } else {
+            self.c1 * (new_val + self.prev_val) / 2.0 + self.c2 * self.filt1 + self.c3 * self.filt2
+        };
+        self.bars += 1;
+        self.prev_val = new_val;
+        self.filt2 = self.filt1;
+        self.filt1 = filt;
+        filt
+    }
+}
+
+// Roofing - Ehlers' roofing filter, a 2 pole high pass removing cycles longer than hp_period
+// followed by a SuperSmoother removing cycles shorter than lp_period, 0 for the first 2 bars
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Roofing {
+    alpha: f64,
+    prices: Container,
+    bars: usize,
+    hp1: f64,
+    hp2: f64,
+    smoother: SuperSmoother,
+}
+
+#[pymethods]
+impl Roofing {
+    #[new]
+    #[pyo3(signature = (hp_period=48, lp_period=10))]
+    pub fn new(hp_period: usize, lp_period: usize) -> Result<Self> {
+        let angle = std::f64::consts::FRAC_1_SQRT_2 * 2.0 * std::f64::consts::PI / hp_period as f64;
+        Ok(Self {
+            alpha: (angle.cos() + angle.sin() - 1.0) / angle.cos(),
+            prices: Container::new(3)?,
+            bars: 0,
+            hp1: 0.0,
+            hp2: 0.0,
+            smoother: SuperSmoother::new(lp_period),
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if !new_val.is_finite() {
+            return f64::NAN;
+        }
+        self.prices.update(new_val);
+        self.bars += 1;
+        let hp = if self.bars < 3 {
+            0.0
+        } else {
+            let k = 1.0 - self.alpha / 2.0;
+            k * k * (new_val - 2.0 * self.prices.get(1) + self.prices.get(0)) + 2.0 * (1.0 - self.alpha) * self.hp1 - (1.0 - self.alpha).powi(2) * self.hp2
+        };
+        self.hp2 = self.hp1;
+        self.hp1 = hp;
+        self.smoother.update(hp)
+    }
+}
+
+// ITrend - Ehlers' Instantaneous Trendline with alpha, returns (itrend, trigger)
+// trigger = 2 * itrend - itrend 2 bars ago, the first 7 bars use a 3 bar fir instead of the iir
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ITrend {
+    alpha: f64,
+    prices: Container,
+    bars: usize,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    itrend: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    itrend1: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    itrend2: f64,
+}
+
+#[pymethods]
+impl ITrend {
+    #[new]
+    #[pyo3(signature = (alpha=0.07))]
+    pub fn new(alpha: f64) -> Result<Self> {
+        Ok(Self {
+            alpha,
+            prices: Container::new(3)?,
+            bars: 0,
+            itrend: f64::NAN,
+            itrend1: f64::NAN,
+            itrend2: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64) {
+        if !new_val.is_finite() {
+            return (f64::NAN, f64::NAN);
+        }
+        self.prices.update(new_val);
+        self.bars += 1;
+        let (price1, price2) = (self.prices.get(1), self.prices.get(0));
+        let a = self.alpha;
+        let itrend = if self.bars < 7 {
+            (new_val + 2.0 * price1 + price2) / 4.0
+        } else {
+            (a - a * a / 4.0) * new_val + 0.5 * a * a * price1 - (a - 0.75 * a * a) * price2 + 2.0 * (1.0 - a) * self.itrend
+                - (1.0 - a).powi(2) * self.itrend1
+        };
+        self.itrend2 = self.itrend1;
+        self.itrend1 = self.itrend;
+        self.itrend = itrend;
+        self.value()
+    }
+}
+
+impl Indicator for HtPhasor {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        if self.core.is_ready() {
+            (self.core.i1, self.core.q1())
+        } else {
+            (f64::NAN, f64::NAN)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+    }
+}
+
+impl Indicator for HtDCPeriod {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.core.is_ready() {
+            self.core.smooth_period
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+    }
+}
+
+impl Indicator for HtDCPhase {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.core.is_ready() {
+            self.dc_phase.phase
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+        self.dc_phase = DCPhase::default();
+    }
+}
+
+impl Indicator for HtSine {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        if self.core.is_ready() {
+            sine_output(self.dc_phase.phase)
+        } else {
+            (f64::NAN, f64::NAN)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+        self.dc_phase = DCPhase::default();
+    }
+}
+
+impl Indicator for HtTrendline {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.core.is_ready() {
+            self.value
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+        self.trendline = Trendline::default();
+        self.value = 0.0;
+    }
+}
+
+impl Indicator for HtTrendMode {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.core.is_ready() {
+            self.trend
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+        self.dc_phase = DCPhase::default();
+        self.trendline = Trendline::default();
+        self.sine = 0.0;
+        self.lead_sine = 0.0;
+        self.days_in_trend = 0;
+        self.trend = 0.0;
+    }
+}
+
+impl Indicator for MAMA {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        if self.core.is_ready() {
+            (self.mama, self.fama)
+        } else {
+            (f64::NAN, f64::NAN)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.core.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.core.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.core.reset();
+        self.prev_phase = 0.0;
+        self.mama = 0.0;
+        self.fama = 0.0;
+    }
+}
+
+impl Indicator for SuperSmoother {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.bars > 0 {
+            self.filt1
+        } else {
+            f64::NAN
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.bars > 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.bars = 0;
+        self.prev_val = 0.0;
+        self.filt1 = 0.0;
+        self.filt2 = 0.0;
+    }
+}
+
+impl Indicator for Roofing {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.smoother.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.smoother.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.prices.reset();
+        self.bars = 0;
+        self.hp1 = 0.0;
+        self.hp2 = 0.0;
+        self.smoother.reset();
+    }
+}
+
+impl Indicator for ITrend {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    // the fir needs 3 prices and the trigger 2 more
+    fn value(&self) -> (f64, f64) {
+        (self.itrend, 2.0 * self.itrend - self.itrend2)
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.itrend2.is_nan()
+    }
+
+    fn warmup_period(&self) -> usize {
+        5
+    }
+
+    fn reset(&mut self) {
+        self.prices.reset();
+        self.bars = 0;
+        self.itrend = f64::NAN;
+        self.itrend1 = f64::NAN;
+        self.itrend2 = f64::NAN;
+    }
+}
+
+impl_snapshot!(HtPhasor, HtDCPeriod, HtDCPhase, HtSine, HtTrendline, HtTrendMode, MAMA, SuperSmoother, Roofing, ITrend);
+impl_batch!(HtPhasor, (new_val) -> (0: f64, 1: f64));
+impl_batch!(HtDCPeriod, (new_val) -> f64);
+impl_batch!(HtDCPhase, (new_val) -> f64);
+impl_batch!(HtSine, (new_val) -> (0: f64, 1: f64));
+impl_batch!(HtTrendline, (new_val) -> f64);
+impl_batch!(HtTrendMode, (new_val) -> f64);
+impl_batch!(MAMA, (new_val) -> (0: f64, 1: f64));
+impl_batch!(SuperSmoother, (new_val) -> f64);
+impl_batch!(Roofing, (new_val) -> f64);
+impl_batch!(ITrend, (new_val) -> (0: f64, 1: f64));
+impl_indicator!(HtPhasor, HtDCPeriod, HtDCPhase, HtSine, HtTrendline, HtTrendMode, MAMA, SuperSmoother, Roofing, ITrend);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // expected outputs come from a line by line python port of the TA-Lib C sources (ta_HT_DCPERIOD.c,
+    // ta_HT_DCPHASE.c, ta_HT_SINE.c, ta_HT_TRENDMODE.c) run from startIdx 0, TA-Lib itself was not available
+
+    // trend plus a 20 and a 7.3 bar cycle
+    const PRICES: [f64; 100] = [
+        100.0, 102.1145, 103.3464, 103.3731, 102.6081, 101.8736, 101.8037, 102.394, 103.0134, 102.8684, 101.5943, 99.5584, 97.6582, 96.751, 97.1062, 98.2563, 99.3476, 99.743, 99.4569, 99.1205,
+        99.5031, 100.9279, 102.9923, 104.7944, 105.5113, 104.9339, 103.5866, 102.3545, 101.8752, 102.1201, 102.4531, 102.1226, 100.8387, 99.0298, 97.5929, 97.3083, 98.321, 100.0487, 101.5783,
+        102.2768, 102.1931, 101.975, 102.3637, 103.6239, 105.3101, 106.5381, 106.5757, 105.3436, 103.4795, 101.9189, 101.2827, 101.494, 101.8858, 101.7198, 100.7493, 99.4297, 98.6268, 99.0222,
+        100.6304, 102.7636, 104.472, 105.1555, 104.9279, 104.4827, 104.5618, 105.3999, 106.5362, 107.1265, 106.5397, 104.8221, 102.7039, 101.1399, 100.6992, 101.2229, 101.9843, 102.233, 101.7429,
+        100.9779, 100.7603, 101.6735, 103.617, 105.8271, 107.3547, 107.6714, 106.9886, 106.0715, 105.6812, 106.0364, 106.6696, 106.7778, 105.82, 103.9432, 101.9342, 100.7261, 100.7976, 101.8789,
+        103.1641, 103.8811, 103.8205, 103.4564,
+    ];
+    // every 4th output from bar 63 on
+    const SAMPLES: [usize; 10] = [63, 67, 71, 75, 79, 83, 87, 91, 95, 99];
+    const DC_PERIOD: [f64; 10] = [
+        22.06979507,
+        22.40086427,
+        20.60794130,
+        20.54390705,
+        21.92263779,
+        20.39146263,
+        20.70362078,
+        21.21695167,
+        20.32813782,
+        22.06684514,
+    ];
+    const DC_PHASE: [f64; 10] = [
+        12.56723850,
+        45.73047331,
+        125.02622288,
+        217.88342021,
+        266.78598126,
+        33.99550106,
+        112.95592468,
+        179.54853864,
+        255.23449099,
+        314.89078588,
+    ];
+    const SINE: [(f64, f64); 10] = [
+        (0.21758518, 0.84402140),
+        (0.71606409, 0.99991873),
+        (0.81888945, 0.17319744),
+        (-0.61405684, -0.99229613),
+        (-0.99842708, -0.74563906),
+        (0.55912780, 0.98161220),
+        (0.92080515, 0.37531973),
+        (0.00787941, -0.70151325),
+        (-0.96697699, -0.86397184),
+        (-0.70845334, -0.00190614),
+    ];
+    // bars 63..100
+    const TREND_MODE: [f64; 37] = [
+        1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0,
+    ];
+
+    fn run<T>(mut update: impl FnMut(f64) -> T) -> Vec<T> {
+        PRICES.iter().map(|&price| update(price)).collect()
+    }
+
+    fn assert_close(value: f64, expected: f64, bar: usize) {
+        assert!((value - expected).abs() < 1e-6, "bar {}: {} != {}", bar, value, expected);
+    }
+
+    #[test]
+    fn dc_period_matches_talib() {
+        let mut indicator = HtDCPeriod::new().unwrap();
+        let output = run(|price| indicator.update(price));
+        assert!(output[..32].iter().all(|v| v.is_nan()));
+        for (&bar, &expected) in SAMPLES.iter().zip(DC_PERIOD.iter()) {
+            assert_close(output[bar], expected, bar);
+        }
+    }
+
+    #[test]
+    fn dc_phase_matches_talib() {
+        let mut indicator = HtDCPhase::new().unwrap();
+        let output = run(|price| indicator.update(price));
+        assert!(output[..63].iter().all(|v| v.is_nan()));
+        for (&bar, &expected) in SAMPLES.iter().zip(DC_PHASE.iter()) {
+            assert_close(output[bar], expected, bar);
+        }
+    }
+
+    #[test]
+    fn sine_matches_talib() {
+        let mut indicator = HtSine::new().unwrap();
+        let output = run(|price| indicator.update(price));
+        for (&bar, &(sine, lead_sine)) in SAMPLES.iter().zip(SINE.iter()) {
+            assert_close(output[bar].0, sine, bar);
+            assert_close(output[bar].1, lead_sine, bar);
+        }
+    }
+
+    #[test]
+    fn trend_mode_matches_talib() {
+        let mut indicator = HtTrendMode::new().unwrap();
+        let output = run(|price| indicator.update(price));
+        assert_eq!(&output[63..], &TREND_MODE[..]);
+        // a reset replays the same series
+        indicator.reset();
+        assert_eq!(&run(|price| indicator.update(price))[63..], &TREND_MODE[..]);
     }
 }
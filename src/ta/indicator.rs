@@ -0,0 +1,52 @@
+// common interface of all ta indicators, so engines and strategies can compose them generically.
+// multiple inputs or outputs are tuples in the same order as the python update() arguments.
+pub trait Indicator {
+    type Input;
+    type Output;
+
+    // feed a new point and return the new output
+    fn update(&mut self, input: Self::Input) -> Self::Output;
+
+    // the current output without feeding a new point
+    fn value(&self) -> Self::Output;
+
+    // true once the output is computed from enough valid points, NaN inputs delay it
+    fn is_ready(&self) -> bool;
+
+    // minimum number of points to become ready
+    fn warmup_period(&self) -> usize;
+
+    // back to the freshly constructed state
+    fn reset(&mut self);
+}
+
+// adds value/is_ready/warmup_period/reset to pyclasses implementing Indicator
+macro_rules! impl_indicator {
+    ($($name:ty),+ $(,)?) => {
+        $(
+            #[pyo3::pymethods]
+            impl $name {
+                #[pyo3(name = "value")]
+                fn py_value(&self) -> <Self as $crate::ta::indicator::Indicator>::Output {
+                    $crate::ta::indicator::Indicator::value(self)
+                }
+
+                #[pyo3(name = "is_ready")]
+                fn py_is_ready(&self) -> bool {
+                    $crate::ta::indicator::Indicator::is_ready(self)
+                }
+
+                #[pyo3(name = "warmup_period")]
+                fn py_warmup_period(&self) -> usize {
+                    $crate::ta::indicator::Indicator::warmup_period(self)
+                }
+
+                #[pyo3(name = "reset")]
+                fn py_reset(&mut self) {
+                    $crate::ta::indicator::Indicator::reset(self)
+                }
+            }
+        )+
+    };
+}
+pub(crate) use impl_indicator;
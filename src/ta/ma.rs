@@ -1,8 +1,14 @@
-use super::rolling::{Container, RollingSum};
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::rolling::{check_period, Container, RollingSum};
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // SMA - Simple Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SMA {
     sumer: RollingSum,
 }
@@ -10,8 +16,8 @@ pub struct SMA {
 #[pymethods]
 impl SMA {
     #[new]
-    pub fn new(period: usize) -> Self {
-        Self { sumer: RollingSum::new(period) }
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self { sumer: RollingSum::new(period)? })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -20,7 +26,8 @@ impl SMA {
 }
 
 // WMA - Weighted Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct WMA {
     container: Container,
     n: f64,
@@ -33,17 +40,17 @@ pub struct WMA {
 #[pymethods]
 impl WMA {
     #[new]
-    pub fn new(period: usize) -> Self {
+    pub fn new(period: usize) -> Result<Self> {
         let n = period as f64;
         let sumn = n * (n + 1.0) / 2.0;
-        Self {
-            container: Container::new(period),
+        Ok(Self {
+            container: Container::new(period)?,
             n,
             sumn,
             nan_count: period,
             sum: 0.0,
             weighted_sum: 0.0,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -64,16 +71,13 @@ impl WMA {
             self.nan_count += 1;
         }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            self.weighted_sum / self.sumn
-        }
+        self.value()
     }
 }
 
 // EMA - Exponential Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EMA {
     alpha: f64,
     ema: Option<f64>,
@@ -82,9 +86,10 @@ pub struct EMA {
 #[pymethods]
 impl EMA {
     #[new]
-    pub fn new(period: usize) -> Self {
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
         let alpha = 2.0 / (period as f64 + 1.0);
-        Self { alpha, ema: None }
+        Ok(Self { alpha, ema: None })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -102,7 +107,8 @@ impl EMA {
 }
 
 // DEMA - Double Exponential Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct DEMA {
     ema_lv1: EMA,
     ema_lv2: EMA,
@@ -111,11 +117,11 @@ pub struct DEMA {
 #[pymethods]
 impl DEMA {
     #[new]
-    pub fn new(period: usize) -> Self {
-        Self {
-            ema_lv1: EMA::new(period),
-            ema_lv2: EMA::new(period),
-        }
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            ema_lv1: EMA::new(period)?,
+            ema_lv2: EMA::new(period)?,
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -127,7 +133,8 @@ impl DEMA {
 }
 
 // RMA - Relative Moving Average, similar to EMA
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RMA {
     period: f64,
     rma: Option<f64>,
@@ -136,8 +143,9 @@ pub struct RMA {
 #[pymethods]
 impl RMA {
     #[new]
-    pub fn new(period: usize) -> Self {
-        Self { period: period as f64, rma: None }
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
+        Ok(Self { period: period as f64, rma: None })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -155,7 +163,8 @@ impl RMA {
 }
 
 // HMA - Hull Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct HMA {
     full_wma: WMA,
     half_wma: WMA,
@@ -165,13 +174,13 @@ pub struct HMA {
 #[pymethods]
 impl HMA {
     #[new]
-    pub fn new(period: usize) -> Self {
+    pub fn new(period: usize) -> Result<Self> {
         let sqrt_period = (period as f64).sqrt().floor() as usize;
-        Self {
-            full_wma: WMA::new(period),
-            half_wma: WMA::new(period / 2),
-            sqrt_wma: WMA::new(sqrt_period),
-        }
+        Ok(Self {
+            full_wma: WMA::new(period)?,
+            half_wma: WMA::new(period / 2)?,
+            sqrt_wma: WMA::new(sqrt_period)?,
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -181,7 +190,8 @@ impl HMA {
 }
 
 // LSMA - Least Squares Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LSMA {
     container: Container,
     n: f64,
@@ -195,19 +205,19 @@ pub struct LSMA {
 #[pymethods]
 impl LSMA {
     #[new]
-    pub fn new(period: usize) -> Self {
+    pub fn new(period: usize) -> Result<Self> {
         let n = period as f64;
         let sumn = n * (n + 1.0) / 2.0;
         let denominator = n * n * (n + 1.0) * (2.0 * n + 1.0) / 6.0 - (sumn).powi(2);
-        Self {
-            container: Container::new(period),
+        Ok(Self {
+            container: Container::new(period)?,
             n,
             sumn,
             denominator,
             nan_count: period,
             sum: 0.0,
             weighted_sum: 0.0,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -228,18 +238,13 @@ impl LSMA {
             self.nan_count += 1;
         }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            let slope = (self.n * self.weighted_sum - self.sumn * self.sum) / self.denominator;
-            let intercept = (self.sum - slope * self.sumn) / self.n;
-            intercept + slope * self.n
-        }
+        self.value()
     }
 }
 
 // VWMA - Volume-Weighted Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct VWMA {
     weighted_sum: RollingSum,
     vol_sum: RollingSum,
@@ -248,11 +253,11 @@ pub struct VWMA {
 #[pymethods]
 impl VWMA {
     #[new]
-    pub fn new(period: usize) -> Self {
-        Self {
-            weighted_sum: RollingSum::new(period),
-            vol_sum: RollingSum::new(period),
-        }
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            weighted_sum: RollingSum::new(period)?,
+            vol_sum: RollingSum::new(period)?,
+        })
     }
 
     pub fn update(&mut self, price: f64, volume: f64) -> f64 {
@@ -263,7 +268,8 @@ impl VWMA {
 }
 
 // ALMA - Arnaud Legoux Moving Average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ALMA {
     container: Container,
     weights_sum: f64,
@@ -277,7 +283,7 @@ impl ALMA {
     // 0: center is oldest point
     // 1: center is newest point
     // sigma usually is 6
-    pub fn new(period: usize, offset: f64, sigma: f64) -> Self {
+    pub fn new(period: usize, offset: f64, sigma: f64) -> Result<Self> {
         let m = offset * (period as f64 - 1.0);
         let s = period as f64 / sigma;
         let mut weights = Vec::with_capacity(period);
@@ -286,25 +292,292 @@ impl ALMA {
             weights.push(w);
         }
 
-        Self {
-            container: Container::new(period),
+        Ok(Self {
+            container: Container::new(period)?,
             weights_sum: weights.iter().sum(),
             weights,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.container.update(new_val);
+        self.value()
+    }
+}
+
+// TEMA - Triple Exponential Moving Average
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TEMA {
+    ema_lv1: EMA,
+    ema_lv2: EMA,
+    ema_lv3: EMA,
+    period: usize,
+    // finite points seen, each EMA in the chain needs period of them
+    count: usize,
+}
+
+#[pymethods]
+impl TEMA {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            ema_lv1: EMA::new(period)?,
+            ema_lv2: EMA::new(period)?,
+            ema_lv3: EMA::new(period)?,
+            period,
+            count: 0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if new_val.is_finite() {
+            self.count += 1;
+        }
+        let lv1 = self.ema_lv1.update(new_val);
+        let lv2 = self.ema_lv2.update(lv1);
+        let lv3 = self.ema_lv3.update(lv2);
+
+        3.0 * lv1 - 3.0 * lv2 + lv3
+    }
+}
+
+// ZLEMA - Zero Lag Exponential Moving Average
+// EMA of the price plus its momentum over (period - 1) / 2 bars
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ZLEMA {
+    container: Container,
+    ema: EMA,
+}
+
+#[pymethods]
+impl ZLEMA {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
+        let lag = (period - 1) / 2;
+        Ok(Self {
+            container: Container::new(lag + 1)?,
+            ema: EMA::new(period)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        let (lagged, _) = self.container.update(new_val);
+        self.ema.update(2.0 * new_val - lagged)
+    }
+}
+
+// KAMA - Kaufman Adaptive Moving Average
+// the efficiency ratio moves the smoothing between the fast (2) and slow (30) EMA constants
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KAMA {
+    container: Container,
+    volatility: RollingSum,
+    fast_sc: f64,
+    slow_sc: f64,
+    kama: Option<f64>,
+}
+
+#[pymethods]
+impl KAMA {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(period + 1)?,
+            volatility: RollingSum::new(period)?,
+            fast_sc: 2.0 / 3.0,
+            slow_sc: 2.0 / 31.0,
+            kama: None,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        let prev_val = self.container.tail();
+        let (oldest, _) = self.container.update(new_val);
+        let volatility = self.volatility.update((new_val - prev_val).abs());
+        let change = (new_val - oldest).abs();
+        // a flat window has no direction
+        let er = if volatility == 0.0 { 0.0 } else { change / volatility };
+
+        if er.is_finite() {
+            let sc = (er * (self.fast_sc - self.slow_sc) + self.slow_sc).powi(2);
+            self.kama = Some(self.kama.map_or(new_val, |prev_kama| prev_kama + sc * (new_val - prev_kama)));
+            self.kama.unwrap()
+        } else {
+            f64::NAN
+        }
+    }
+}
+
+// T3 - Tillson T3 Moving Average, six chained EMAs with volume factor 0.7
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct T3 {
+    emas: Vec<EMA>,
+    coeffs: [f64; 4],
+    period: usize,
+    // finite points seen, each EMA in the chain needs period of them
+    count: usize,
+}
+
+#[pymethods]
+impl T3 {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        let v: f64 = 0.7;
+        let coeffs = [-v.powi(3), 3.0 * v.powi(2) + 3.0 * v.powi(3), -6.0 * v.powi(2) - 3.0 * v - 3.0 * v.powi(3), 1.0 + 3.0 * v + v.powi(3) + 3.0 * v.powi(2)];
+        Ok(Self {
+            emas: (0..6).map(|_| EMA::new(period)).collect::<Result<_>>()?,
+            coeffs,
+            period,
+            count: 0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if new_val.is_finite() {
+            self.count += 1;
+        }
+        let mut val = new_val;
+        let mut lvs = [0.0; 6];
+        for (lv, ema) in lvs.iter_mut().zip(self.emas.iter_mut()) {
+            val = ema.update(val);
+            *lv = val;
+        }
+        self.coeffs[0] * lvs[5] + self.coeffs[1] * lvs[4] + self.coeffs[2] * lvs[3] + self.coeffs[3] * lvs[2]
+    }
+}
+
+// McGinley Dynamic, speeds up when the price runs away from the line
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct McGinley {
+    period: f64,
+    md: Option<f64>,
+}
+
+#[pymethods]
+impl McGinley {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
+        Ok(Self { period: period as f64, md: None })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        if new_val.is_finite() {
+            if let Some(prev_md) = self.md {
+                // a zero line would never move again
+                let ratio = if prev_md == 0.0 { 1.0 } else { new_val / prev_md };
+                self.md = Some(prev_md + (new_val - prev_md) / (self.period * ratio.powi(4)));
+            } else {
+                self.md = Some(new_val);
+            }
+            self.md.unwrap()
+        } else {
+            f64::NAN
         }
     }
+}
+
+// FRAMA - Fractal Adaptive Moving Average
+// the fractal dimension of the window sets alpha, period should be even
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FRAMA {
+    container: Container,
+    frama: Option<f64>,
+}
+
+#[pymethods]
+impl FRAMA {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(period)?,
+            frama: None,
+        })
+    }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
         self.container.update(new_val);
-        let weighted_sum: f64 = self.container.iter().zip(self.weights.iter()).map(|(p, w)| p * w).sum();
-        weighted_sum / self.weights_sum
+        if !self.container.is_valid() {
+            return f64::NAN;
+        }
+
+        let n = self.container.len();
+        let half = n / 2;
+        let range = |skip: usize, take: usize| {
+            let (min, max) = self.container.iter().skip(skip).take(take).fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &x| (min.min(x), max.max(x)));
+            max - min
+        };
+        let n1 = range(0, half) / half as f64;
+        let n2 = range(n - half, half) / half as f64;
+        let n3 = range(0, n) / n as f64;
+        // a flat window is treated as a line
+        let dimension = if n1 + n2 > 0.0 && n3 > 0.0 { ((n1 + n2).ln() - n3.ln()) / 2f64.ln() } else { 1.0 };
+        let alpha = (-4.6 * (dimension - 1.0)).exp().clamp(0.01, 1.0);
+
+        self.frama = Some(self.frama.map_or(new_val, |prev_frama| alpha * new_val + (1.0 - alpha) * prev_frama));
+        self.frama.unwrap()
+    }
+}
+
+// VIDYA - Variable Index Dynamic Average
+// EMA whose alpha is scaled by the absolute Chande Momentum Oscillator over the same period
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct VIDYA {
+    alpha: f64,
+    gain_sum: RollingSum,
+    loss_sum: RollingSum,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_val: f64,
+    vidya: Option<f64>,
+}
+
+#[pymethods]
+impl VIDYA {
+    #[new]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            alpha: 2.0 / (period as f64 + 1.0),
+            gain_sum: RollingSum::new(period)?,
+            loss_sum: RollingSum::new(period)?,
+            prev_val: f64::NAN,
+            vidya: None,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        let diff = new_val - self.prev_val;
+        self.prev_val = new_val;
+        // f64::max would turn a NaN diff into 0
+        let (gain, loss) = if diff.is_nan() { (f64::NAN, f64::NAN) } else { (diff.max(0.0), (-diff).max(0.0)) };
+        let gains = self.gain_sum.update(gain);
+        let losses = self.loss_sum.update(loss);
+        let cmo = if gains + losses == 0.0 { 0.0 } else { (gains - losses) / (gains + losses) };
+
+        if cmo.is_finite() {
+            let k = self.alpha * cmo.abs();
+            self.vidya = Some(self.vidya.map_or(new_val, |prev_vidya| k * new_val + (1.0 - k) * prev_vidya));
+            self.vidya.unwrap()
+        } else {
+            f64::NAN
+        }
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MA {
     inner: MAType,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 enum MAType {
     Simple(SMA),
     Weighted(WMA),
@@ -313,27 +586,81 @@ enum MAType {
     Hull(HMA),
     Relative(RMA),
     LeastSquares(LSMA),
+    VolumeWeighted(VWMA),
+    ArnaudLegoux(ALMA),
+    TripleExponential(TEMA),
+    ZeroLag(ZLEMA),
+    KaufmanAdaptive(KAMA),
+    Tillson(T3),
+    McGinley(McGinley),
+    FractalAdaptive(FRAMA),
+    VariableIndex(VIDYA),
+}
+
+// every variant implements Indicator, only the update signature of VWMA differs
+macro_rules! dispatch {
+    ($inner:expr, $ma:ident => $call:expr) => {
+        match $inner {
+            MAType::Simple($ma) => $call,
+            MAType::Weighted($ma) => $call,
+            MAType::Exponential($ma) => $call,
+            MAType::DoubleExponential($ma) => $call,
+            MAType::Hull($ma) => $call,
+            MAType::Relative($ma) => $call,
+            MAType::LeastSquares($ma) => $call,
+            MAType::VolumeWeighted($ma) => $call,
+            MAType::ArnaudLegoux($ma) => $call,
+            MAType::TripleExponential($ma) => $call,
+            MAType::ZeroLag($ma) => $call,
+            MAType::KaufmanAdaptive($ma) => $call,
+            MAType::Tillson($ma) => $call,
+            MAType::McGinley($ma) => $call,
+            MAType::FractalAdaptive($ma) => $call,
+            MAType::VariableIndex($ma) => $call,
+        }
+    };
 }
 
 #[pymethods]
 impl MA {
     #[new]
-    pub fn new(window: usize, method: &str) -> Self {
+    pub fn new(window: usize, method: &str) -> Result<Self> {
+        // hma also averages over half the window
+        let min_window = if method == "hma" { 2 } else { 1 };
+        if window < min_window {
+            return Err(Error::InvalidParameter(format!("{} window must be at least {}, got {}", method, min_window, window)));
+        }
         let inner = match method {
-            "sma" => MAType::Simple(SMA::new(window)),
-            "wma" => MAType::Weighted(WMA::new(window)),
-            "ema" => MAType::Exponential(EMA::new(window)),
-            "dema" => MAType::DoubleExponential(DEMA::new(window)),
-            "hma" => MAType::Hull(HMA::new(window)),
-            "rma" => MAType::Relative(RMA::new(window)),
-            "lsma" => MAType::LeastSquares(LSMA::new(window)),
-            _ => panic!("Invalid method"),
+            "sma" => MAType::Simple(SMA::new(window)?),
+            "wma" => MAType::Weighted(WMA::new(window)?),
+            "ema" => MAType::Exponential(EMA::new(window)?),
+            "dema" => MAType::DoubleExponential(DEMA::new(window)?),
+            "hma" => MAType::Hull(HMA::new(window)?),
+            "rma" => MAType::Relative(RMA::new(window)?),
+            "lsma" => MAType::LeastSquares(LSMA::new(window)?),
+            "vwma" => MAType::VolumeWeighted(VWMA::new(window)?),
+            "alma" => MAType::ArnaudLegoux(ALMA::new(window, 0.85, 6.0)?),
+            "tema" => MAType::TripleExponential(TEMA::new(window)?),
+            "zlema" => MAType::ZeroLag(ZLEMA::new(window)?),
+            "kama" => MAType::KaufmanAdaptive(KAMA::new(window)?),
+            "t3" => MAType::Tillson(T3::new(window)?),
+            "mcginley" => MAType::McGinley(McGinley::new(window)?),
+            "frama" => MAType::FractalAdaptive(FRAMA::new(window)?),
+            "vidya" => MAType::VariableIndex(VIDYA::new(window)?),
+            _ => return Err(Error::InvalidParameter(format!("unknown ma_type {}", method))),
         };
-        MA { inner }
+        Ok(MA { inner })
     }
 
+    // vwma sees a unit volume, i.e. it is a sma
     pub fn update(&mut self, new_val: f64) -> f64 {
+        self.update_with_volume(new_val, 1.0)
+    }
+
+    // volume is only used by vwma
+    pub fn update_with_volume(&mut self, new_val: f64, volume: f64) -> f64 {
         match &mut self.inner {
+            MAType::VolumeWeighted(vwma) => vwma.update(new_val, volume),
             MAType::Simple(sma) => sma.update(new_val),
             MAType::Weighted(wma) => wma.update(new_val),
             MAType::Exponential(ema) => ema.update(new_val),
@@ -341,6 +668,497 @@ impl MA {
             MAType::Hull(hma) => hma.update(new_val),
             MAType::Relative(rma) => rma.update(new_val),
             MAType::LeastSquares(lsma) => lsma.update(new_val),
+            MAType::ArnaudLegoux(alma) => alma.update(new_val),
+            MAType::TripleExponential(tema) => tema.update(new_val),
+            MAType::ZeroLag(zlema) => zlema.update(new_val),
+            MAType::KaufmanAdaptive(kama) => kama.update(new_val),
+            MAType::Tillson(t3) => t3.update(new_val),
+            MAType::McGinley(md) => md.update(new_val),
+            MAType::FractalAdaptive(frama) => frama.update(new_val),
+            MAType::VariableIndex(vidya) => vidya.update(new_val),
         }
     }
 }
+
+impl Indicator for SMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.sumer.value() / self.sumer.container.len() as f64
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sumer.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.sumer.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.sumer.reset();
+    }
+}
+
+impl Indicator for WMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.nan_count > 0 {
+            f64::NAN
+        } else {
+            self.weighted_sum / self.sumn
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+        self.sum = 0.0;
+        self.weighted_sum = 0.0;
+    }
+}
+
+// NaN inputs return NaN but leave the average untouched, value() is the average itself
+impl Indicator for EMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.ema.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ema.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.ema = None;
+    }
+}
+
+impl Indicator for DEMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        2.0 * self.ema_lv1.value() - self.ema_lv2.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ema_lv2.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.ema_lv1.reset();
+        self.ema_lv2.reset();
+    }
+}
+
+// same as EMA, NaN inputs leave the average untouched
+impl Indicator for RMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.rma.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.rma.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.rma = None;
+    }
+}
+
+impl Indicator for HMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.sqrt_wma.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sqrt_wma.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.full_wma.warmup_period() + self.sqrt_wma.warmup_period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.full_wma.reset();
+        self.half_wma.reset();
+        self.sqrt_wma.reset();
+    }
+}
+
+impl Indicator for LSMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.nan_count > 0 {
+            f64::NAN
+        } else {
+            let slope = (self.n * self.weighted_sum - self.sumn * self.sum) / self.denominator;
+            let intercept = (self.sum - slope * self.sumn) / self.n;
+            intercept + slope * self.n
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+        self.sum = 0.0;
+        self.weighted_sum = 0.0;
+    }
+}
+
+impl Indicator for VWMA {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (price, volume): (f64, f64)) -> f64 {
+        Self::update(self, price, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.weighted_sum.value() / self.vol_sum.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.weighted_sum.is_ready() && self.vol_sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.vol_sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.weighted_sum.reset();
+        self.vol_sum.reset();
+    }
+}
+
+impl Indicator for ALMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        let weighted_sum: f64 = self.container.iter().zip(self.weights.iter()).map(|(p, w)| p * w).sum();
+        weighted_sum / self.weights_sum
+    }
+
+    fn is_ready(&self) -> bool {
+        self.container.is_valid()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+    }
+}
+
+impl Indicator for TEMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        3.0 * self.ema_lv1.value() - 3.0 * self.ema_lv2.value() + self.ema_lv3.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.count >= self.warmup_period()
+    }
+
+    // three chained EMAs of period points each, same lookback as TA-Lib
+    fn warmup_period(&self) -> usize {
+        3 * self.period - 2
+    }
+
+    fn reset(&mut self) {
+        self.ema_lv1.reset();
+        self.ema_lv2.reset();
+        self.ema_lv3.reset();
+        self.count = 0;
+    }
+}
+
+impl Indicator for ZLEMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.ema.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ema.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.ema.reset();
+    }
+}
+
+// same as EMA, NaN inputs leave the average untouched
+impl Indicator for KAMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.kama.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.kama.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.volatility.reset();
+        self.kama = None;
+    }
+}
+
+impl Indicator for T3 {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.coeffs[0] * self.emas[5].value() + self.coeffs[1] * self.emas[4].value() + self.coeffs[2] * self.emas[3].value() + self.coeffs[3] * self.emas[2].value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.count >= self.warmup_period()
+    }
+
+    // six chained EMAs of period points each, same lookback as TA-Lib
+    fn warmup_period(&self) -> usize {
+        6 * self.period - 5
+    }
+
+    fn reset(&mut self) {
+        self.emas.iter_mut().for_each(|ema| ema.reset());
+        self.count = 0;
+    }
+}
+
+// same as EMA, NaN inputs leave the line untouched
+impl Indicator for McGinley {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.md.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.md.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.md = None;
+    }
+}
+
+impl Indicator for FRAMA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.frama.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.frama.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.frama = None;
+    }
+}
+
+impl Indicator for VIDYA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.vidya.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.vidya.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.gain_sum.warmup_period() + 1
+    }
+
+    fn reset(&mut self) {
+        self.gain_sum.reset();
+        self.loss_sum.reset();
+        self.prev_val = f64::NAN;
+        self.vidya = None;
+    }
+}
+
+impl Indicator for MA {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        dispatch!(&self.inner, ma => ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        dispatch!(&self.inner, ma => ma.is_ready())
+    }
+
+    fn warmup_period(&self) -> usize {
+        dispatch!(&self.inner, ma => ma.warmup_period())
+    }
+
+    fn reset(&mut self) {
+        dispatch!(&mut self.inner, ma => ma.reset())
+    }
+}
+
+impl_snapshot!(SMA, WMA, EMA, DEMA, RMA, HMA, LSMA, VWMA, ALMA, TEMA, ZLEMA, KAMA, T3, McGinley, FRAMA, VIDYA, MA);
+impl_batch!(SMA, (new_val) -> f64);
+impl_batch!(WMA, (new_val) -> f64);
+impl_batch!(EMA, (new_val) -> f64);
+impl_batch!(DEMA, (new_val) -> f64);
+impl_batch!(RMA, (new_val) -> f64);
+impl_batch!(HMA, (new_val) -> f64);
+impl_batch!(LSMA, (new_val) -> f64);
+impl_batch!(VWMA, (price, volume) -> f64);
+impl_batch!(ALMA, (new_val) -> f64);
+impl_batch!(TEMA, (new_val) -> f64);
+impl_batch!(ZLEMA, (new_val) -> f64);
+impl_batch!(KAMA, (new_val) -> f64);
+impl_batch!(T3, (new_val) -> f64);
+impl_batch!(McGinley, (new_val) -> f64);
+impl_batch!(FRAMA, (new_val) -> f64);
+impl_batch!(VIDYA, (new_val) -> f64);
+impl_batch!(MA, (new_val) -> f64);
+impl_indicator!(SMA, WMA, EMA, DEMA, RMA, HMA, LSMA, VWMA, ALMA, TEMA, ZLEMA, KAMA, T3, McGinley, FRAMA, VIDYA, MA);
@@ -0,0 +1,297 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::ma::MA;
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// every class returns (line, signal, histogram), the signal is a ma of the line
+// and the histogram is line - signal
+fn line_output(line: f64, signal: f64) -> (f64, f64, f64) {
+    (line, signal, line - signal)
+}
+
+// shared by MACD, APO and PPO: the line compares a fast and a slow ma of price
+#[derive(Clone, Serialize, Deserialize)]
+struct PriceOscillator {
+    fast_ma: MA,
+    slow_ma: MA,
+    signal_ma: MA,
+    // PPO divides the difference by the slow ma
+    percent: bool,
+}
+
+impl PriceOscillator {
+    fn new(fast_period: usize, slow_period: usize, signal_period: usize, ma_type: &str, signal_ma_type: &str, percent: bool) -> Result<Self> {
+        Ok(Self {
+            fast_ma: MA::new(fast_period, ma_type)?,
+            slow_ma: MA::new(slow_period, ma_type)?,
+            signal_ma: MA::new(signal_period, signal_ma_type)?,
+            percent,
+        })
+    }
+
+    fn line(&self) -> f64 {
+        let diff = self.fast_ma.value() - self.slow_ma.value();
+        if !self.percent {
+            return diff;
+        }
+        let slow = self.slow_ma.value();
+        if slow == 0.0 {
+            0.0
+        } else {
+            100.0 * diff / slow
+        }
+    }
+
+    fn update(&mut self, new_val: f64) -> (f64, f64, f64) {
+        let fast = self.fast_ma.update(new_val);
+        let slow = self.slow_ma.update(new_val);
+        // a skipped bar must not feed the previous line into the signal again
+        if fast.is_nan() || slow.is_nan() {
+            return line_output(f64::NAN, f64::NAN);
+        }
+        let line = self.line();
+        line_output(line, self.signal_ma.update(line))
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        line_output(self.line(), self.signal_ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.fast_ma.is_ready() && self.slow_ma.is_ready() && self.signal_ma.is_ready()
+    }
+
+    // the signal starts once both lines are ready
+    fn warmup_period(&self) -> usize {
+        self.fast_ma.warmup_period().max(self.slow_ma.warmup_period()) + self.signal_ma.warmup_period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.fast_ma.reset();
+        self.slow_ma.reset();
+        self.signal_ma.reset();
+    }
+}
+
+// MACD - Moving Average Convergence/Divergence, fast ema - slow ema
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MACD {
+    inner: PriceOscillator,
+}
+
+#[pymethods]
+impl MACD {
+    #[new]
+    #[pyo3(signature = (fast_period=12, slow_period=26, signal_period=9, ma_type="ema", signal_ma_type="ema"))]
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize, ma_type: &str, signal_ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            inner: PriceOscillator::new(fast_period, slow_period, signal_period, ma_type, signal_ma_type, false)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64) {
+        self.inner.update(new_val)
+    }
+}
+
+// APO - Absolute Price Oscillator, fast ma - slow ma with TA-Lib's sma default
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct APO {
+    inner: PriceOscillator,
+}
+
+#[pymethods]
+impl APO {
+    #[new]
+    #[pyo3(signature = (fast_period=12, slow_period=26, signal_period=9, ma_type="sma", signal_ma_type="ema"))]
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize, ma_type: &str, signal_ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            inner: PriceOscillator::new(fast_period, slow_period, signal_period, ma_type, signal_ma_type, false)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64) {
+        self.inner.update(new_val)
+    }
+}
+
+// PPO - Percentage Price Oscillator, 100 * (fast ma - slow ma) / slow ma, 0 for a zero slow ma
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PPO {
+    inner: PriceOscillator,
+}
+
+#[pymethods]
+impl PPO {
+    #[new]
+    #[pyo3(signature = (fast_period=12, slow_period=26, signal_period=9, ma_type="sma", signal_ma_type="ema"))]
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize, ma_type: &str, signal_ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            inner: PriceOscillator::new(fast_period, slow_period, signal_period, ma_type, signal_ma_type, true)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64) {
+        self.inner.update(new_val)
+    }
+}
+
+// TRIX - 1-bar percent rate of change of a triple smoothed ma
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TRIX {
+    ma1: MA,
+    ma2: MA,
+    ma3: MA,
+    signal_ma: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_triple: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    line: f64,
+}
+
+#[pymethods]
+impl TRIX {
+    #[new]
+    #[pyo3(signature = (period=15, signal_period=9, ma_type="ema", signal_ma_type="ema"))]
+    pub fn new(period: usize, signal_period: usize, ma_type: &str, signal_ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            ma1: MA::new(period, ma_type)?,
+            ma2: MA::new(period, ma_type)?,
+            ma3: MA::new(period, ma_type)?,
+            signal_ma: MA::new(signal_period, signal_ma_type)?,
+            prev_triple: f64::NAN,
+            line: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64) {
+        let triple = self.ma3.update(self.ma2.update(self.ma1.update(new_val)));
+        if triple.is_nan() {
+            return line_output(f64::NAN, f64::NAN);
+        }
+        self.line = 100.0 * (triple - self.prev_triple) / self.prev_triple;
+        self.prev_triple = triple;
+        line_output(self.line, self.signal_ma.update(self.line))
+    }
+}
+
+impl Indicator for MACD {
+    type Input = f64;
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        self.inner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for APO {
+    type Input = f64;
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        self.inner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for PPO {
+    type Input = f64;
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        self.inner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for TRIX {
+    type Input = f64;
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        line_output(self.line, self.signal_ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.signal_ma.is_ready()
+    }
+
+    // three chained ma, one more bar for the change, then the signal
+    fn warmup_period(&self) -> usize {
+        self.ma1.warmup_period() + self.ma2.warmup_period() + self.ma3.warmup_period() - 2 + self.signal_ma.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.ma1.reset();
+        self.ma2.reset();
+        self.ma3.reset();
+        self.signal_ma.reset();
+        self.prev_triple = f64::NAN;
+        self.line = f64::NAN;
+    }
+}
+
+impl_snapshot!(MACD, APO, PPO, TRIX);
+impl_batch!(MACD, (new_val) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(APO, (new_val) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(PPO, (new_val) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(TRIX, (new_val) -> (0: f64, 1: f64, 2: f64));
+impl_indicator!(MACD, APO, PPO, TRIX);
+
@@ -1,11 +1,18 @@
 use pyo3::prelude::*;
+mod batch;
+pub mod indicator;
+mod provisional;
+pub mod bands;
 pub mod cross;
 pub mod cumulative;
 pub mod cycle;
 pub mod ma;
+pub mod macd;
 pub mod momentum;
 pub mod rolling;
+pub mod trend;
 pub mod volatility;
+pub mod volume;
 pub mod savgol;
 
 pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -13,9 +20,21 @@ pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
     ta.add_class::<rolling::RollingSum>()?;
     ta.add_class::<rolling::RollingMax>()?;
     ta.add_class::<rolling::RollingMin>()?;
+    ta.add_class::<rolling::RollingArgMax>()?;
+    ta.add_class::<rolling::RollingArgMin>()?;
+    ta.add_class::<rolling::RollingMinMax>()?;
     ta.add_class::<rolling::RollingQuantile>()?;
     ta.add_class::<rolling::RollingRank>()?;
     ta.add_class::<rolling::RollingMean>()?;
+    ta.add_class::<rolling::RollingVariance>()?;
+    ta.add_class::<rolling::RollingStdev>()?;
+    ta.add_class::<rolling::RollingSkew>()?;
+    ta.add_class::<rolling::RollingKurt>()?;
+    ta.add_class::<rolling::RollingZScore>()?;
+    ta.add_class::<rolling::RollingCovariance>()?;
+    ta.add_class::<rolling::RollingCorrelation>()?;
+    ta.add_class::<rolling::RollingBeta>()?;
+    ta.add_class::<rolling::RollingLinReg>()?;
     ta.add_class::<cumulative::CumMin>()?;
     ta.add_class::<cumulative::CumMax>()?;
     ta.add_class::<cumulative::CumMinMax>()?;
@@ -31,12 +50,66 @@ pub fn register(parent_module: &Bound<'_, PyModule>) -> PyResult<()> {
     ta.add_class::<ma::LSMA>()?;
     ta.add_class::<ma::VWMA>()?;
     ta.add_class::<ma::ALMA>()?;
+    ta.add_class::<ma::TEMA>()?;
+    ta.add_class::<ma::ZLEMA>()?;
+    ta.add_class::<ma::KAMA>()?;
+    ta.add_class::<ma::T3>()?;
+    ta.add_class::<ma::McGinley>()?;
+    ta.add_class::<ma::FRAMA>()?;
+    ta.add_class::<ma::VIDYA>()?;
     ta.add_class::<ma::MA>()?;
     ta.add_class::<cross::Crosser>()?;
     ta.add_class::<volatility::ATR>()?;
     ta.add_class::<volatility::NATR>()?;
+    ta.add_class::<volatility::Parkinson>()?;
+    ta.add_class::<volatility::GarmanKlass>()?;
+    ta.add_class::<volatility::RogersSatchell>()?;
+    ta.add_class::<volatility::YangZhang>()?;
+    ta.add_class::<volatility::HistoricalVolatility>()?;
+    ta.add_class::<volatility::GARCH>()?;
     ta.add_class::<momentum::CCI>()?;
     ta.add_class::<momentum::KDJ>()?;
+    ta.add_class::<momentum::RSI>()?;
+    ta.add_class::<momentum::Stoch>()?;
+    ta.add_class::<momentum::StochRSI>()?;
+    ta.add_class::<momentum::WilliamsR>()?;
+    ta.add_class::<momentum::ROC>()?;
+    ta.add_class::<momentum::CMO>()?;
+    ta.add_class::<momentum::TSI>()?;
+    ta.add_class::<momentum::UltimateOscillator>()?;
+    ta.add_class::<macd::MACD>()?;
+    ta.add_class::<macd::APO>()?;
+    ta.add_class::<macd::PPO>()?;
+    ta.add_class::<macd::TRIX>()?;
+    ta.add_class::<trend::ADX>()?;
+    ta.add_class::<trend::Aroon>()?;
+    ta.add_class::<trend::PSAR>()?;
+    ta.add_class::<trend::SuperTrend>()?;
+    ta.add_class::<trend::Vortex>()?;
+    ta.add_class::<cycle::HtPhasor>()?;
+    ta.add_class::<cycle::HtDCPeriod>()?;
+    ta.add_class::<cycle::HtDCPhase>()?;
+    ta.add_class::<cycle::HtSine>()?;
+    ta.add_class::<cycle::HtTrendline>()?;
+    ta.add_class::<cycle::HtTrendMode>()?;
+    ta.add_class::<cycle::MAMA>()?;
+    ta.add_class::<cycle::SuperSmoother>()?;
+    ta.add_class::<cycle::Roofing>()?;
+    ta.add_class::<cycle::ITrend>()?;
+    ta.add_class::<bands::Bollinger>()?;
+    ta.add_class::<bands::Keltner>()?;
+    ta.add_class::<bands::Donchian>()?;
+    ta.add_class::<bands::Envelope>()?;
+    ta.add_class::<volume::OBV>()?;
+    ta.add_class::<volume::MFI>()?;
+    ta.add_class::<volume::AD>()?;
+    ta.add_class::<volume::CMF>()?;
+    ta.add_class::<volume::ForceIndex>()?;
+    ta.add_class::<volume::EaseOfMovement>()?;
+    ta.add_class::<volume::AnchoredVWAP>()?;
     ta.add_class::<savgol::Savgol>()?;
+    ta.add_class::<provisional::Provisional>()?;
+    // register as bktrader.ta so that pickle can import the classes
+    parent_module.py().import("sys")?.getattr("modules")?.set_item("bktrader.ta", &ta)?;
     parent_module.add_submodule(&ta)
 }
@@ -1,52 +1,65 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
 use super::{
     ma::MA,
-    rolling::{RollingMax, RollingMin},
+    rolling::{check_period, Container, RollingMax, RollingMin, RollingSum},
 };
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // CCI - Commodity Channel Index
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct CCI {
     tp_meaner: MA,
     deviation_meaner: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    tp: f64,
 }
 
 #[pymethods]
 impl CCI {
     #[new]
     #[pyo3(signature = (ma_period=14, ma_type="sma"))]
-    pub fn new(ma_period: usize, ma_type: &str) -> Self {
-        Self {
-            tp_meaner: MA::new(ma_period, ma_type),
-            deviation_meaner: MA::new(ma_period, ma_type),
-        }
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            tp_meaner: MA::new(ma_period, ma_type)?,
+            deviation_meaner: MA::new(ma_period, ma_type)?,
+            tp: f64::NAN,
+        })
     }
 
     pub fn update(&mut self, high: f64, low: f64, close: f64) -> f64 {
-        let tp = (high + low + close) / 3.0;
-        let tp_avg = self.tp_meaner.update(tp);
-        let deviation_avg = self.deviation_meaner.update((tp - tp_avg).abs());
-        (tp - tp_avg) / deviation_avg
+        self.tp = (high + low + close) / 3.0;
+        let tp_avg = self.tp_meaner.update(self.tp);
+        self.deviation_meaner.update((self.tp - tp_avg).abs());
+        self.value()
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct KDJ {
     low_miner: RollingMin,
     high_maxer: RollingMax,
     smoother: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    k_line: f64,
 }
 
 #[pymethods]
 impl KDJ {
     #[new]
     #[pyo3(signature = (minmax_period=9,ma_period=3, ma_type="sma"))]
-    pub fn new(minmax_period: usize, ma_period: usize, ma_type: &str) -> Self {
-        Self {
-            low_miner: RollingMin::new(minmax_period),
-            high_maxer: RollingMax::new(minmax_period),
-            smoother: MA::new(ma_period, ma_type),
-        }
+    pub fn new(minmax_period: usize, ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            low_miner: RollingMin::new(minmax_period)?,
+            high_maxer: RollingMax::new(minmax_period)?,
+            smoother: MA::new(ma_period, ma_type)?,
+            k_line: f64::NAN,
+        })
     }
 
     // price maybe close or vwap
@@ -54,9 +67,644 @@ impl KDJ {
         let lowest_low = self.low_miner.update(low);
         let highest_high = self.high_maxer.update(high);
         let k_line = (price - lowest_low) / (highest_high - lowest_low);
+        self.k_line = k_line;
         let d_line = self.smoother.update(k_line);
         let j_line = 3.0 * k_line - 2.0 * d_line;
 
         (k_line, d_line, j_line)
     }
 }
+
+// share of the up part scaled to [0, 100], a flat window (0/0) is neutral
+fn oscillator(up: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        50.0
+    } else {
+        100.0 * up / total
+    }
+}
+
+// split a change into gain and loss, f64::max would turn a NaN change into 0
+fn gain_loss(diff: f64) -> (f64, f64) {
+    if diff.is_nan() {
+        (f64::NAN, f64::NAN)
+    } else {
+        (diff.max(0.0), (-diff).max(0.0))
+    }
+}
+
+// RSI - Relative Strength Index in [0, 100], rma is Wilder's smoothing
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RSI {
+    gain_ma: MA,
+    loss_ma: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_val: f64,
+    // wilder's rma starts from the simple average of the first period changes (as TA-Lib), 0 for other ma types
+    seed_period: usize,
+    seed_count: usize,
+    seed_gain: f64,
+    seed_loss: f64,
+}
+
+#[pymethods]
+impl RSI {
+    #[new]
+    #[pyo3(signature = (ma_period=14, ma_type="rma"))]
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            gain_ma: MA::new(ma_period, ma_type)?,
+            loss_ma: MA::new(ma_period, ma_type)?,
+            prev_val: f64::NAN,
+            seed_period: if ma_type == "rma" { ma_period } else { 0 },
+            seed_count: 0,
+            seed_gain: 0.0,
+            seed_loss: 0.0,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        let (mut gain, mut loss) = gain_loss(new_val - self.prev_val);
+        self.prev_val = new_val;
+        if self.seed_count < self.seed_period {
+            // NaN changes are skipped, the same as rma does
+            if gain.is_finite() {
+                self.seed_count += 1;
+                self.seed_gain += gain;
+                self.seed_loss += loss;
+            }
+            if self.seed_count < self.seed_period {
+                return f64::NAN;
+            }
+            gain = self.seed_gain / self.seed_period as f64;
+            loss = self.seed_loss / self.seed_period as f64;
+        }
+        let avg_gain = self.gain_ma.update(gain);
+        let avg_loss = self.loss_ma.update(loss);
+        oscillator(avg_gain, avg_gain + avg_loss)
+    }
+}
+
+// Stoch - Stochastic Oscillator, slow %K (smoothed fast %K) and %D in [0, 100]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Stoch {
+    low_miner: RollingMin,
+    high_maxer: RollingMax,
+    k_smoother: MA,
+    d_smoother: MA,
+}
+
+#[pymethods]
+impl Stoch {
+    #[new]
+    #[pyo3(signature = (minmax_period=14, k_period=3, d_period=3, ma_type="sma"))]
+    pub fn new(minmax_period: usize, k_period: usize, d_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            low_miner: RollingMin::new(minmax_period)?,
+            high_maxer: RollingMax::new(minmax_period)?,
+            k_smoother: MA::new(k_period, ma_type)?,
+            d_smoother: MA::new(d_period, ma_type)?,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> (f64, f64) {
+        let lowest_low = self.low_miner.update(low);
+        let highest_high = self.high_maxer.update(high);
+        let k_line = self.k_smoother.update(oscillator(close - lowest_low, highest_high - lowest_low));
+        let d_line = self.d_smoother.update(k_line);
+        (k_line, d_line)
+    }
+}
+
+// StochRSI - Stochastic Oscillator applied to RSI, (%K, %D) in [0, 100]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StochRSI {
+    rsi: RSI,
+    low_miner: RollingMin,
+    high_maxer: RollingMax,
+    k_smoother: MA,
+    d_smoother: MA,
+}
+
+#[pymethods]
+impl StochRSI {
+    #[new]
+    #[pyo3(signature = (rsi_period=14, minmax_period=14, k_period=3, d_period=3, ma_type="sma"))]
+    pub fn new(rsi_period: usize, minmax_period: usize, k_period: usize, d_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            rsi: RSI::new(rsi_period, "rma")?,
+            low_miner: RollingMin::new(minmax_period)?,
+            high_maxer: RollingMax::new(minmax_period)?,
+            k_smoother: MA::new(k_period, ma_type)?,
+            d_smoother: MA::new(d_period, ma_type)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64) {
+        let rsi = self.rsi.update(new_val);
+        let lowest_rsi = self.low_miner.update(rsi);
+        let highest_rsi = self.high_maxer.update(rsi);
+        let k_line = self.k_smoother.update(oscillator(rsi - lowest_rsi, highest_rsi - lowest_rsi));
+        let d_line = self.d_smoother.update(k_line);
+        (k_line, d_line)
+    }
+}
+
+// WilliamsR - Williams %R in [-100, 0]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WilliamsR {
+    low_miner: RollingMin,
+    high_maxer: RollingMax,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    close: f64,
+}
+
+#[pymethods]
+impl WilliamsR {
+    #[new]
+    #[pyo3(signature = (minmax_period=14))]
+    pub fn new(minmax_period: usize) -> Result<Self> {
+        Ok(Self {
+            low_miner: RollingMin::new(minmax_period)?,
+            high_maxer: RollingMax::new(minmax_period)?,
+            close: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64) -> f64 {
+        self.low_miner.update(low);
+        self.high_maxer.update(high);
+        self.close = close;
+        self.value()
+    }
+}
+
+// ROC - Rate of Change in percent over period bars
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ROC {
+    // head is the value period bars ago, tail the newest one
+    container: Container,
+}
+
+#[pymethods]
+impl ROC {
+    #[new]
+    #[pyo3(signature = (period=10))]
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
+        Ok(Self {
+            container: Container::new(period + 1)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.container.update(new_val);
+        self.value()
+    }
+}
+
+// CMO - Chande Momentum Oscillator in [-100, 100], plain sums of gains and losses over the period
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CMO {
+    gain_sum: RollingSum,
+    loss_sum: RollingSum,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_val: f64,
+}
+
+#[pymethods]
+impl CMO {
+    #[new]
+    #[pyo3(signature = (period=14))]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            gain_sum: RollingSum::new(period)?,
+            loss_sum: RollingSum::new(period)?,
+            prev_val: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        let (gain, loss) = gain_loss(new_val - self.prev_val);
+        self.prev_val = new_val;
+        self.gain_sum.update(gain);
+        self.loss_sum.update(loss);
+        self.value()
+    }
+}
+
+// TSI - True Strength Index in [-100, 100] and its signal line,
+// the change and its absolute value are both smoothed by a long then a short ma
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TSI {
+    long_ma: MA,
+    short_ma: MA,
+    abs_long_ma: MA,
+    abs_short_ma: MA,
+    signal_ma: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_val: f64,
+}
+
+#[pymethods]
+impl TSI {
+    #[new]
+    #[pyo3(signature = (long_period=25, short_period=13, signal_period=13, ma_type="ema"))]
+    pub fn new(long_period: usize, short_period: usize, signal_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            long_ma: MA::new(long_period, ma_type)?,
+            short_ma: MA::new(short_period, ma_type)?,
+            abs_long_ma: MA::new(long_period, ma_type)?,
+            abs_short_ma: MA::new(short_period, ma_type)?,
+            signal_ma: MA::new(signal_period, ma_type)?,
+            prev_val: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64) {
+        let diff = new_val - self.prev_val;
+        self.prev_val = new_val;
+        let smoothed = self.short_ma.update(self.long_ma.update(diff));
+        let abs_smoothed = self.abs_short_ma.update(self.abs_long_ma.update(diff.abs()));
+        let tsi = if abs_smoothed == 0.0 { 0.0 } else { 100.0 * smoothed / abs_smoothed };
+        let signal = self.signal_ma.update(tsi);
+        (tsi, signal)
+    }
+}
+
+// UltimateOscillator - Ultimate Oscillator in [0, 100], buying pressure over true range on three windows weighted 4:2:1
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UltimateOscillator {
+    fast_bp_sum: RollingSum,
+    fast_tr_sum: RollingSum,
+    mid_bp_sum: RollingSum,
+    mid_tr_sum: RollingSum,
+    slow_bp_sum: RollingSum,
+    slow_tr_sum: RollingSum,
+}
+
+#[pymethods]
+impl UltimateOscillator {
+    #[new]
+    #[pyo3(signature = (fast_period=7, mid_period=14, slow_period=28))]
+    pub fn new(fast_period: usize, mid_period: usize, slow_period: usize) -> Result<Self> {
+        Ok(Self {
+            fast_bp_sum: RollingSum::new(fast_period)?,
+            fast_tr_sum: RollingSum::new(fast_period)?,
+            mid_bp_sum: RollingSum::new(mid_period)?,
+            mid_tr_sum: RollingSum::new(mid_period)?,
+            slow_bp_sum: RollingSum::new(slow_period)?,
+            slow_tr_sum: RollingSum::new(slow_period)?,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, preclose: f64) -> f64 {
+        let true_low = low.min(preclose);
+        let bp = close - true_low;
+        let tr = high.max(preclose) - true_low;
+        self.fast_bp_sum.update(bp);
+        self.fast_tr_sum.update(tr);
+        self.mid_bp_sum.update(bp);
+        self.mid_tr_sum.update(tr);
+        self.slow_bp_sum.update(bp);
+        self.slow_tr_sum.update(tr);
+        self.value()
+    }
+}
+
+impl Indicator for CCI {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close): (f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close)
+    }
+
+    fn value(&self) -> f64 {
+        (self.tp - self.tp_meaner.value()) / self.deviation_meaner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.deviation_meaner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.tp_meaner.warmup_period() + self.deviation_meaner.warmup_period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.tp_meaner.reset();
+        self.deviation_meaner.reset();
+        self.tp = f64::NAN;
+    }
+}
+
+impl Indicator for KDJ {
+    type Input = (f64, f64, f64);
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, (price, high, low): (f64, f64, f64)) -> (f64, f64, f64) {
+        Self::update(self, price, high, low)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        let d_line = self.smoother.value();
+        (self.k_line, d_line, 3.0 * self.k_line - 2.0 * d_line)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.low_miner.is_ready() && self.high_maxer.is_ready() && self.smoother.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.high_maxer.warmup_period() + self.smoother.warmup_period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.low_miner.reset();
+        self.high_maxer.reset();
+        self.smoother.reset();
+        self.k_line = f64::NAN;
+    }
+}
+
+// like EMA, a NaN input returns NaN while value() keeps the last averages
+impl Indicator for RSI {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        let avg_gain = self.gain_ma.value();
+        oscillator(avg_gain, avg_gain + self.loss_ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.gain_ma.is_ready() && self.loss_ma.is_ready()
+    }
+
+    // the first point has no change, rma waits for its seed average
+    fn warmup_period(&self) -> usize {
+        self.gain_ma.warmup_period().max(self.seed_period) + 1
+    }
+
+    fn reset(&mut self) {
+        self.gain_ma.reset();
+        self.loss_ma.reset();
+        self.prev_val = f64::NAN;
+        self.seed_count = 0;
+        self.seed_gain = 0.0;
+        self.seed_loss = 0.0;
+    }
+}
+
+impl Indicator for Stoch {
+    type Input = (f64, f64, f64);
+    type Output = (f64, f64);
+
+    fn update(&mut self, (high, low, close): (f64, f64, f64)) -> (f64, f64) {
+        Self::update(self, high, low, close)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        (self.k_smoother.value(), self.d_smoother.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.low_miner.is_ready() && self.high_maxer.is_ready() && self.k_smoother.is_ready() && self.d_smoother.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.high_maxer.warmup_period() + self.k_smoother.warmup_period() + self.d_smoother.warmup_period() - 2
+    }
+
+    fn reset(&mut self) {
+        self.low_miner.reset();
+        self.high_maxer.reset();
+        self.k_smoother.reset();
+        self.d_smoother.reset();
+    }
+}
+
+impl Indicator for StochRSI {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        (self.k_smoother.value(), self.d_smoother.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.rsi.is_ready() && self.low_miner.is_ready() && self.high_maxer.is_ready() && self.k_smoother.is_ready() && self.d_smoother.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.rsi.warmup_period() + self.high_maxer.warmup_period() + self.k_smoother.warmup_period() + self.d_smoother.warmup_period() - 3
+    }
+
+    fn reset(&mut self) {
+        self.rsi.reset();
+        self.low_miner.reset();
+        self.high_maxer.reset();
+        self.k_smoother.reset();
+        self.d_smoother.reset();
+    }
+}
+
+impl Indicator for WilliamsR {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close): (f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close)
+    }
+
+    fn value(&self) -> f64 {
+        let lowest_low = self.low_miner.value();
+        oscillator(self.close - lowest_low, self.high_maxer.value() - lowest_low) - 100.0
+    }
+
+    fn is_ready(&self) -> bool {
+        self.low_miner.is_ready() && self.high_maxer.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.high_maxer.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.low_miner.reset();
+        self.high_maxer.reset();
+        self.close = f64::NAN;
+    }
+}
+
+impl Indicator for ROC {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        100.0 * (self.container.tail() / self.container.head() - 1.0)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.container.is_valid()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+    }
+}
+
+impl Indicator for CMO {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        let gains = self.gain_sum.value();
+        2.0 * oscillator(gains, gains + self.loss_sum.value()) - 100.0
+    }
+
+    fn is_ready(&self) -> bool {
+        self.gain_sum.is_ready() && self.loss_sum.is_ready()
+    }
+
+    // the first point has no change
+    fn warmup_period(&self) -> usize {
+        self.gain_sum.warmup_period() + 1
+    }
+
+    fn reset(&mut self) {
+        self.gain_sum.reset();
+        self.loss_sum.reset();
+        self.prev_val = f64::NAN;
+    }
+}
+
+impl Indicator for TSI {
+    type Input = f64;
+    type Output = (f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        let abs_smoothed = self.abs_short_ma.value();
+        let tsi = if abs_smoothed == 0.0 { 0.0 } else { 100.0 * self.short_ma.value() / abs_smoothed };
+        (tsi, self.signal_ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.short_ma.is_ready() && self.abs_short_ma.is_ready() && self.signal_ma.is_ready()
+    }
+
+    // the first point has no change
+    fn warmup_period(&self) -> usize {
+        self.long_ma.warmup_period() + self.short_ma.warmup_period() + self.signal_ma.warmup_period() - 1
+    }
+
+    fn reset(&mut self) {
+        self.long_ma.reset();
+        self.short_ma.reset();
+        self.abs_long_ma.reset();
+        self.abs_short_ma.reset();
+        self.signal_ma.reset();
+        self.prev_val = f64::NAN;
+    }
+}
+
+impl Indicator for UltimateOscillator {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close, preclose): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        let fast = oscillator(self.fast_bp_sum.value(), self.fast_tr_sum.value());
+        let mid = oscillator(self.mid_bp_sum.value(), self.mid_tr_sum.value());
+        let slow = oscillator(self.slow_bp_sum.value(), self.slow_tr_sum.value());
+        (4.0 * fast + 2.0 * mid + slow) / 7.0
+    }
+
+    fn is_ready(&self) -> bool {
+        self.fast_tr_sum.is_ready() && self.mid_tr_sum.is_ready() && self.slow_tr_sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.fast_tr_sum.warmup_period().max(self.mid_tr_sum.warmup_period()).max(self.slow_tr_sum.warmup_period())
+    }
+
+    fn reset(&mut self) {
+        self.fast_bp_sum.reset();
+        self.fast_tr_sum.reset();
+        self.mid_bp_sum.reset();
+        self.mid_tr_sum.reset();
+        self.slow_bp_sum.reset();
+        self.slow_tr_sum.reset();
+    }
+}
+
+impl_snapshot!(CCI, KDJ, RSI, Stoch, StochRSI, WilliamsR, ROC, CMO, TSI, UltimateOscillator);
+impl_batch!(CCI, (high, low, close) -> f64);
+impl_batch!(KDJ, (price, high, low) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(RSI, (new_val) -> f64);
+impl_batch!(Stoch, (high, low, close) -> (0: f64, 1: f64));
+impl_batch!(StochRSI, (new_val) -> (0: f64, 1: f64));
+impl_batch!(WilliamsR, (high, low, close) -> f64);
+impl_batch!(ROC, (new_val) -> f64);
+impl_batch!(CMO, (new_val) -> f64);
+impl_batch!(TSI, (new_val) -> (0: f64, 1: f64));
+impl_batch!(UltimateOscillator, (high, low, close, preclose) -> f64);
+impl_indicator!(CCI, KDJ, RSI, Stoch, StochRSI, WilliamsR, ROC, CMO, TSI, UltimateOscillator);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wilder's example series, the expected values are TA-Lib RSI(14)
+    #[test]
+    fn rsi_rma_seeds_with_simple_average() {
+        let closes = [
+            44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61, 46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64,
+        ];
+        let expected = [70.464, 66.249, 66.481, 69.347, 66.295, 57.915];
+        let mut rsi = RSI::new(14, "rma").unwrap();
+        assert_eq!(rsi.warmup_period(), 15);
+        let output: Vec<f64> = closes.iter().map(|&close| rsi.update(close)).collect();
+        assert!(output[..14].iter().all(|v| v.is_nan()));
+        for (value, expected) in output[14..].iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+        }
+        assert!(rsi.is_ready());
+    }
+}
@@ -0,0 +1,112 @@
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+// provisional (intraday) updates for any ta indicator: the bar still being formed is applied to a copy of the
+// committed indicator, so it can be replaced by later provisional values. commit() replays the last provisional
+// update on the committed indicator, which stays the very object passed in, so the caller's handle keeps up.
+// pickle/copy only: the wrapped indicator is any python object, so there is no to_bytes/to_json snapshot here,
+// the indicator pickles itself with its own snapshot.
+#[pyclass(module = "bktrader.ta")]
+pub struct Provisional {
+    committed: Py<PyAny>,
+    provisional: Option<Py<PyAny>>,
+    // args of the last provisional update, replayed on commit
+    pending: Option<Py<PyTuple>>,
+}
+
+// provisional indicator and pending args, the committed indicator is the constructor argument
+type PickleState = (Option<Py<PyAny>>, Option<Py<PyTuple>>);
+
+impl Provisional {
+    // the provisional indicator if any, else the committed one
+    fn current_ref(&self) -> &Py<PyAny> {
+        self.provisional.as_ref().unwrap_or(&self.committed)
+    }
+}
+
+#[pymethods]
+impl Provisional {
+    #[new]
+    pub fn new(indicator: Py<PyAny>) -> Self {
+        Self {
+            committed: indicator,
+            provisional: None,
+            pending: None,
+        }
+    }
+
+    #[getter]
+    pub fn current(&self, py: Python<'_>) -> Py<PyAny> {
+        self.current_ref().clone_ref(py)
+    }
+
+    #[getter]
+    pub fn committed(&self, py: Python<'_>) -> Py<PyAny> {
+        self.committed.clone_ref(py)
+    }
+
+    #[getter]
+    pub fn is_provisional(&self) -> bool {
+        self.provisional.is_some()
+    }
+
+    // a final update replaces the pending provisional one
+    #[pyo3(signature = (*args))]
+    pub fn update<'py>(&mut self, py: Python<'py>, args: &Bound<'py, PyTuple>) -> PyResult<Bound<'py, PyAny>> {
+        self.rollback();
+        self.committed.bind(py).call_method1("update", args)
+    }
+
+    // replaces the previous provisional update, the committed indicator is untouched
+    #[pyo3(signature = (*args))]
+    pub fn update_provisional<'py>(&mut self, py: Python<'py>, args: &Bound<'py, PyTuple>) -> PyResult<Bound<'py, PyAny>> {
+        let provisional = self.committed.bind(py).call_method0("__copy__")?;
+        let output = provisional.call_method1("update", args)?;
+        self.provisional = Some(provisional.unbind());
+        self.pending = Some(args.clone().unbind());
+        Ok(output)
+    }
+
+    // keep the last provisional update as final
+    pub fn commit(&mut self, py: Python<'_>) -> PyResult<()> {
+        if let Some(args) = self.pending.take() {
+            self.provisional = None;
+            self.committed.bind(py).call_method1("update", args.bind(py))?;
+        }
+        Ok(())
+    }
+
+    pub fn rollback(&mut self) {
+        self.provisional = None;
+        self.pending = None;
+    }
+
+    pub fn value<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        self.current_ref().bind(py).call_method0("value")
+    }
+
+    pub fn is_ready(&self, py: Python<'_>) -> PyResult<bool> {
+        self.current_ref().bind(py).call_method0("is_ready")?.extract()
+    }
+
+    pub fn warmup_period(&self, py: Python<'_>) -> PyResult<usize> {
+        self.committed.bind(py).call_method0("warmup_period")?.extract()
+    }
+
+    pub fn reset(&mut self, py: Python<'_>) -> PyResult<()> {
+        self.rollback();
+        self.committed.bind(py).call_method0("reset")?;
+        Ok(())
+    }
+
+    fn __reduce__<'py>(slf: &Bound<'py, Self>) -> PyResult<(Bound<'py, PyAny>, (Py<PyAny>,), PickleState)> {
+        let py = slf.py();
+        let this = slf.borrow();
+        let state = (this.provisional.as_ref().map(|p| p.clone_ref(py)), this.pending.as_ref().map(|p| p.clone_ref(py)));
+        Ok((slf.as_any().get_type().into_any(), (this.committed.clone_ref(py),), state))
+    }
+
+    fn __setstate__(&mut self, state: PickleState) {
+        (self.provisional, self.pending) = state;
+    }
+}
@@ -1,4 +1,10 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 pub struct ContainerIter<'a> {
     buf: &'a [f64],
@@ -21,19 +27,43 @@ impl<'a> Iterator for ContainerIter<'a> {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Container {
+    #[serde(with = "crate::snapshot::nan_vec_as_null")]
     buf: Vec<f64>,
     head_idx: usize,
     tail_idx: usize,
 }
 
+// a window needs at least one point, a zero length would panic on the first update
+pub fn check_period(n: usize) -> Result<()> {
+    if n == 0 {
+        return Err(Error::InvalidParameter("period must be at least 1, got 0".to_string()));
+    }
+    Ok(())
+}
+
+// a quantile outside [0, 1] would index past the sorted window
+pub fn check_quantile(quantile: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&quantile) {
+        return Err(Error::InvalidParameter(format!("quantile must be in [0, 1], got {}", quantile)));
+    }
+    Ok(())
+}
+
 impl Container {
-    pub fn new(n: usize) -> Self {
-        Self {
-            buf: vec![f64::NAN; n],
+    pub fn new(n: usize) -> Result<Self> {
+        Self::filled(n, f64::NAN)
+    }
+
+    // starts with n copies of value instead of NaN
+    pub fn filled(n: usize, value: f64) -> Result<Self> {
+        check_period(n)?;
+        Ok(Self {
+            buf: vec![value; n],
             head_idx: 0,
             tail_idx: 0,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> (f64, f64) {
@@ -61,6 +91,21 @@ impl Container {
         self.buf.len()
     }
 
+    // no NaN inside the window
+    pub fn is_valid(&self) -> bool {
+        self.buf.iter().all(|v| !v.is_nan())
+    }
+
+    pub fn reset(&mut self) {
+        self.reset_with(f64::NAN);
+    }
+
+    pub fn reset_with(&mut self, value: f64) {
+        self.buf.fill(value);
+        self.head_idx = 0;
+        self.tail_idx = 0;
+    }
+
     pub fn iter(&self) -> ContainerIter<'_> {
         ContainerIter {
             buf: &self.buf,
@@ -70,7 +115,8 @@ impl Container {
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RollingSum {
     pub container: Container,
     nan_count: usize,
@@ -80,12 +126,12 @@ pub struct RollingSum {
 #[pymethods]
 impl RollingSum {
     #[new]
-    pub fn new(n: usize) -> Self {
-        Self {
-            container: Container::new(n),
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(n)?,
             nan_count: n,
             sum: 0.0,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -104,16 +150,13 @@ impl RollingSum {
             self.nan_count += 1;
         }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            self.sum
-        }
+        self.value()
     }
 }
 
 // no NAN rolling average
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RollingMean {
     container: Container,
     nan_count: usize,
@@ -123,12 +166,12 @@ pub struct RollingMean {
 #[pymethods]
 impl RollingMean {
     #[new]
-    pub fn new(n: usize) -> Self {
-        Self {
-            container: Container::new(n),
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(n)?,
             nan_count: n,
             sum: 0.0,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -147,87 +190,200 @@ impl RollingMean {
             self.nan_count += 1;
         }
 
-        if self.nan_count > 0 {
-            self.sum / (self.container.len() - self.nan_count) as f64
-        } else {
-            self.sum / self.container.len() as f64
+        self.value()
+    }
+}
+
+// window max (or min) in O(1) amortized: the deque keeps the candidates, older ones dominated by
+// a newer value can never be the extreme again, so values strictly decrease (increase) from front to back.
+// the window is NaN while it holds any NaN, ties report the newest bar.
+#[derive(Clone, Serialize, Deserialize)]
+struct RollingExtreme {
+    n: usize,
+    is_max: bool,
+    // index of the newest bar
+    idx: u64,
+    // consecutive valid bars ending at the newest one, capped at n
+    valid_run: usize,
+    candidates: VecDeque<(u64, f64)>,
+}
+
+impl RollingExtreme {
+    fn new(n: usize, is_max: bool) -> Result<Self> {
+        check_period(n)?;
+        Ok(Self {
+            n,
+            is_max,
+            idx: 0,
+            valid_run: 0,
+            candidates: VecDeque::with_capacity(n),
+        })
+    }
+
+    fn update(&mut self, new_val: f64) {
+        self.idx += 1;
+        if new_val.is_nan() {
+            self.valid_run = 0;
+            // nothing before a NaN can be reported until it leaves the window
+            self.candidates.clear();
+            return;
         }
+        self.valid_run = (self.valid_run + 1).min(self.n);
+        while let Some(&(_, back)) = self.candidates.back() {
+            let dominated = if self.is_max { back <= new_val } else { back >= new_val };
+            if !dominated {
+                break;
+            }
+            self.candidates.pop_back();
+        }
+        self.candidates.push_back((self.idx, new_val));
+        while let Some(&(front_idx, _)) = self.candidates.front() {
+            if front_idx + self.n as u64 > self.idx {
+                break;
+            }
+            self.candidates.pop_front();
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.valid_run >= self.n
+    }
+
+    fn value(&self) -> f64 {
+        match self.candidates.front() {
+            Some(&(_, v)) if self.is_ready() => v,
+            _ => f64::NAN,
+        }
+    }
+
+    // 0 when the newest bar is the extreme
+    fn bars_since(&self) -> f64 {
+        match self.candidates.front() {
+            Some(&(idx, _)) if self.is_ready() => (self.idx - idx) as f64,
+            _ => f64::NAN,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.idx = 0;
+        self.valid_run = 0;
+        self.candidates.clear();
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RollingMax {
-    container: Container,
-    nan_count: usize,
+    inner: RollingExtreme,
 }
 
 #[pymethods]
 impl RollingMax {
     #[new]
-    pub fn new(n: usize) -> Self {
-        Self {
-            container: Container::new(n),
-            nan_count: n,
-        }
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingExtreme::new(n, true)?,
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
-        let old_val = self.container.head();
-        self.container.update(new_val);
+        self.inner.update(new_val);
+        self.inner.value()
+    }
+}
 
-        if new_val.is_nan() {
-            self.nan_count += 1;
-        }
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingMin {
+    inner: RollingExtreme,
+}
 
-        if old_val.is_nan() {
-            self.nan_count -= 1;
-        }
+#[pymethods]
+impl RollingMin {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingExtreme::new(n, false)?,
+        })
+    }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            self.container.iter().fold(f64::NAN, |cur_max, x| if *x <= cur_max { cur_max } else { *x })
-        }
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.inner.value()
     }
 }
 
-#[pyclass]
-pub struct RollingMin {
-    container: Container,
-    nan_count: usize,
+// bars since the window max, 0 is the newest bar
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingArgMax {
+    inner: RollingExtreme,
 }
 
 #[pymethods]
-impl RollingMin {
+impl RollingArgMax {
     #[new]
-    pub fn new(n: usize) -> Self {
-        Self {
-            container: Container::new(n),
-            nan_count: n,
-        }
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingExtreme::new(n, true)?,
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
-        let old_val = self.container.head();
-        self.container.update(new_val);
+        self.inner.update(new_val);
+        self.inner.bars_since()
+    }
+}
 
-        if new_val.is_nan() {
-            self.nan_count += 1;
-        }
+// bars since the window min, 0 is the newest bar
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingArgMin {
+    inner: RollingExtreme,
+}
 
-        if old_val.is_nan() {
-            self.nan_count -= 1;
-        }
+#[pymethods]
+impl RollingArgMin {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingExtreme::new(n, false)?,
+        })
+    }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            self.container.iter().fold(f64::NAN, |cur_min, x| if *x >= cur_min { cur_min } else { *x })
-        }
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.inner.bars_since()
+    }
+}
+
+// (min, max, bars since min, bars since max) of the window in one pass
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingMinMax {
+    min: RollingExtreme,
+    max: RollingExtreme,
+}
+
+#[pymethods]
+impl RollingMinMax {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            min: RollingExtreme::new(n, false)?,
+            max: RollingExtreme::new(n, true)?,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> (f64, f64, f64, f64) {
+        self.min.update(new_val);
+        self.max.update(new_val);
+        self.value()
     }
 }
 
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RollingQuantile {
     container: Container,
     dataset: Vec<f64>,
@@ -238,13 +394,14 @@ pub struct RollingQuantile {
 #[pymethods]
 impl RollingQuantile {
     #[new]
-    pub fn new(n: usize, quantile: f64) -> Self {
-        Self {
-            container: Container::new(n),
+    pub fn new(n: usize, quantile: f64) -> Result<Self> {
+        check_quantile(quantile)?;
+        Ok(Self {
+            container: Container::new(n)?,
             dataset: Vec::new(),
             nan_count: n,
             quantile,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -267,21 +424,11 @@ impl RollingQuantile {
             self.nan_count -= 1;
         }
 
-        if self.nan_count > 0 {
-            f64::NAN
-        } else {
-            let index = (self.dataset.len() - 1) as f64 * self.quantile;
-            let lower_index = index.floor() as usize;
-            let fraction = index - lower_index as f64;
-
-            let lower_value = self.dataset[lower_index];
-            let upper_value = if lower_index + 1 < self.dataset.len() { self.dataset[lower_index + 1] } else { lower_value };
-
-            lower_value + fraction * (upper_value - lower_value)
-        }
+        self.value()
     }
 }
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RollingRank {
     container: Container,
     nan_count: usize,
@@ -290,11 +437,11 @@ pub struct RollingRank {
 #[pymethods]
 impl RollingRank {
     #[new]
-    pub fn new(n: usize) -> Self {
-        Self {
-            container: Container::new(n),
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(n)?,
             nan_count: n,
-        }
+        })
     }
 
     pub fn update(&mut self, new_val: f64) -> f64 {
@@ -309,12 +456,1007 @@ impl RollingRank {
             self.nan_count -= 1;
         }
 
+        self.value()
+    }
+}
+
+// streaming central moments of the valid points, updated with Welford/Pébay one point merges
+// remove() is the inverse of add(), so sliding a window costs O(1)
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    fn add(&mut self, x: f64) {
+        let n_prev = self.n;
+        let n = n_prev + 1.0;
+        let delta = x - self.mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * n_prev;
+        self.mean += delta_n;
+        self.m4 += term * delta_n2 * (n * n - 3.0 * n + 3.0) + 6.0 * delta_n2 * self.m2 - 4.0 * delta_n * self.m3;
+        self.m3 += term * delta_n * (n - 2.0) - 3.0 * delta_n * self.m2;
+        self.m2 += term;
+        self.n = n;
+    }
+
+    fn remove(&mut self, x: f64) {
+        let n = self.n;
+        let n_prev = n - 1.0;
+        if n_prev <= 0.0 {
+            *self = Self::default();
+            return;
+        }
+        let mean = self.mean - (x - self.mean) / n_prev;
+        let delta = x - mean;
+        let delta_n = delta / n;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * n_prev;
+        let m2 = (self.m2 - term).max(0.0);
+        let m3 = self.m3 - term * delta_n * (n - 2.0) + 3.0 * delta_n * m2;
+        self.m4 = self.m4 - term * delta_n2 * (n * n - 3.0 * n + 3.0) - 6.0 * delta_n2 * m2 + 4.0 * delta_n * m3;
+        self.m3 = m3;
+        self.m2 = m2;
+        self.mean = mean;
+        self.n = n_prev;
+    }
+
+    fn variance(&self, ddof: usize) -> f64 {
+        if self.n <= ddof as f64 {
+            f64::NAN
+        } else {
+            self.m2 / (self.n - ddof as f64)
+        }
+    }
+
+    fn is_flat(&self) -> bool {
+        is_flat(self.m2, self.n, self.mean)
+    }
+}
+
+// a constant window leaves rounding residue in m2 after removals
+fn is_flat(m2: f64, n: f64, mean: f64) -> bool {
+    m2 <= 1e-14 * n * mean * mean
+}
+
+// window of a single input feeding Moments with the same NaN handling as RollingSum,
+// the moments are rebuilt from the window every n updates to stop rounding drift
+#[derive(Clone, Serialize, Deserialize)]
+struct RollingMoments {
+    container: Container,
+    nan_count: usize,
+    moments: Moments,
+    updates: usize,
+}
+
+impl RollingMoments {
+    fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            container: Container::new(n)?,
+            nan_count: n,
+            moments: Moments::default(),
+            updates: 0,
+        })
+    }
+
+    fn update(&mut self, new_val: f64) {
+        let old_val = self.container.head();
+        self.container.update(new_val);
+
+        if old_val.is_finite() {
+            self.moments.remove(old_val);
+        } else {
+            self.nan_count -= 1;
+        }
+
+        if new_val.is_finite() {
+            self.moments.add(new_val);
+        } else {
+            self.nan_count += 1;
+        }
+
+        self.updates += 1;
+        if self.updates >= self.container.len() {
+            self.updates = 0;
+            self.moments = Moments::default();
+            self.container.iter().filter(|x| x.is_finite()).for_each(|&x| self.moments.add(x));
+        }
+    }
+
+    // NaN while the window has a NaN
+    fn moments(&self) -> Option<&Moments> {
         if self.nan_count > 0 {
+            None
+        } else {
+            Some(&self.moments)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+        self.moments = Moments::default();
+        self.updates = 0;
+    }
+}
+
+// streaming co-moments of (x, y) pairs, Welford style like Moments
+#[derive(Clone, Default, Serialize, Deserialize)]
+struct CoMoments {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    m2_x: f64,
+    m2_y: f64,
+    c_xy: f64,
+}
+
+impl CoMoments {
+    fn add(&mut self, x: f64, y: f64) {
+        self.n += 1.0;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / self.n;
+        self.mean_y += dy / self.n;
+        self.m2_x += dx * (x - self.mean_x);
+        self.m2_y += dy * (y - self.mean_y);
+        self.c_xy += dx * (y - self.mean_y);
+    }
+
+    fn remove(&mut self, x: f64, y: f64) {
+        let n_prev = self.n - 1.0;
+        if n_prev <= 0.0 {
+            *self = Self::default();
+            return;
+        }
+        let mean_x = self.mean_x - (x - self.mean_x) / n_prev;
+        let mean_y = self.mean_y - (y - self.mean_y) / n_prev;
+        let dx = x - mean_x;
+        let dy = y - mean_y;
+        self.m2_x = (self.m2_x - dx * (x - self.mean_x)).max(0.0);
+        self.m2_y = (self.m2_y - dy * (y - self.mean_y)).max(0.0);
+        self.c_xy -= dx * (y - self.mean_y);
+        self.mean_x = mean_x;
+        self.mean_y = mean_y;
+        self.n = n_prev;
+    }
+
+    // slope of y regressed on x, NaN for a flat x
+    fn slope(&self) -> f64 {
+        if is_flat(self.m2_x, self.n, self.mean_x) {
             f64::NAN
         } else {
-            let last_element = self.container.tail();
-            let count = self.container.iter().filter(|&&x| x < last_element).count();
-            count as f64 / self.container.len() as f64
+            self.c_xy / self.m2_x
+        }
+    }
+
+    fn correlation(&self) -> f64 {
+        if is_flat(self.m2_x, self.n, self.mean_x) || is_flat(self.m2_y, self.n, self.mean_y) {
+            f64::NAN
+        } else {
+            (self.c_xy / (self.m2_x * self.m2_y).sqrt()).clamp(-1.0, 1.0)
+        }
+    }
+}
+
+// window of (x, y) pairs feeding CoMoments, a pair with a NaN counts as NaN
+#[derive(Clone, Serialize, Deserialize)]
+struct RollingCoMoments {
+    x_container: Container,
+    y_container: Container,
+    nan_count: usize,
+    moments: CoMoments,
+    updates: usize,
+}
+
+impl RollingCoMoments {
+    fn new(n: usize) -> Result<Self> {
+        Ok(Self {
+            x_container: Container::new(n)?,
+            y_container: Container::new(n)?,
+            nan_count: n,
+            moments: CoMoments::default(),
+            updates: 0,
+        })
+    }
+
+    fn update(&mut self, x: f64, y: f64) {
+        let (old_x, old_y) = (self.x_container.head(), self.y_container.head());
+        let (x, y) = if x.is_finite() && y.is_finite() { (x, y) } else { (f64::NAN, f64::NAN) };
+        self.x_container.update(x);
+        self.y_container.update(y);
+
+        if old_x.is_finite() {
+            self.moments.remove(old_x, old_y);
+        } else {
+            self.nan_count -= 1;
+        }
+
+        if x.is_finite() {
+            self.moments.add(x, y);
+        } else {
+            self.nan_count += 1;
+        }
+
+        self.updates += 1;
+        if self.updates >= self.x_container.len() {
+            self.updates = 0;
+            self.moments = CoMoments::default();
+            for (&x, &y) in self.x_container.iter().zip(self.y_container.iter()) {
+                if x.is_finite() {
+                    self.moments.add(x, y);
+                }
+            }
+        }
+    }
+
+    fn moments(&self) -> Option<&CoMoments> {
+        if self.nan_count > 0 {
+            None
+        } else {
+            Some(&self.moments)
         }
     }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.x_container.len()
+    }
+
+    fn reset(&mut self) {
+        self.x_container.reset();
+        self.y_container.reset();
+        self.nan_count = self.x_container.len();
+        self.moments = CoMoments::default();
+        self.updates = 0;
+    }
+}
+
+// variance with ddof delta degrees of freedom, 1 for the sample variance like pandas
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingVariance {
+    inner: RollingMoments,
+    ddof: usize,
+}
+
+#[pymethods]
+impl RollingVariance {
+    #[new]
+    #[pyo3(signature = (n, ddof=1))]
+    pub fn new(n: usize, ddof: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingMoments::new(n)?,
+            ddof,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.value()
+    }
+}
+
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingStdev {
+    inner: RollingMoments,
+    ddof: usize,
+}
+
+#[pymethods]
+impl RollingStdev {
+    #[new]
+    #[pyo3(signature = (n, ddof=1))]
+    pub fn new(n: usize, ddof: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingMoments::new(n)?,
+            ddof,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.value()
+    }
+}
+
+// bias corrected sample skewness like pandas, NaN for a flat window
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingSkew {
+    inner: RollingMoments,
+}
+
+#[pymethods]
+impl RollingSkew {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self { inner: RollingMoments::new(n)? })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.value()
+    }
 }
+
+// bias corrected sample excess kurtosis like pandas, NaN for a flat window
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingKurt {
+    inner: RollingMoments,
+}
+
+#[pymethods]
+impl RollingKurt {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self { inner: RollingMoments::new(n)? })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.value()
+    }
+}
+
+// distance of the newest point from the window mean in stdevs, 0 for a flat window
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingZScore {
+    inner: RollingMoments,
+    ddof: usize,
+}
+
+#[pymethods]
+impl RollingZScore {
+    #[new]
+    #[pyo3(signature = (n, ddof=1))]
+    pub fn new(n: usize, ddof: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingMoments::new(n)?,
+            ddof,
+        })
+    }
+
+    pub fn update(&mut self, new_val: f64) -> f64 {
+        self.inner.update(new_val);
+        self.value()
+    }
+}
+
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingCovariance {
+    inner: RollingCoMoments,
+    ddof: usize,
+}
+
+#[pymethods]
+impl RollingCovariance {
+    #[new]
+    #[pyo3(signature = (n, ddof=1))]
+    pub fn new(n: usize, ddof: usize) -> Result<Self> {
+        Ok(Self {
+            inner: RollingCoMoments::new(n)?,
+            ddof,
+        })
+    }
+
+    pub fn update(&mut self, x: f64, y: f64) -> f64 {
+        self.inner.update(x, y);
+        self.value()
+    }
+}
+
+// pearson correlation, NaN if either input is flat
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingCorrelation {
+    inner: RollingCoMoments,
+}
+
+#[pymethods]
+impl RollingCorrelation {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self { inner: RollingCoMoments::new(n)? })
+    }
+
+    pub fn update(&mut self, x: f64, y: f64) -> f64 {
+        self.inner.update(x, y);
+        self.value()
+    }
+}
+
+// beta of y against a benchmark x, cov(x, y) / var(x)
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingBeta {
+    inner: RollingCoMoments,
+}
+
+#[pymethods]
+impl RollingBeta {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self { inner: RollingCoMoments::new(n)? })
+    }
+
+    pub fn update(&mut self, y: f64, x: f64) -> f64 {
+        self.inner.update(x, y);
+        self.value()
+    }
+}
+
+// least squares y = slope * x + intercept, returns (slope, intercept, r2)
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RollingLinReg {
+    inner: RollingCoMoments,
+}
+
+#[pymethods]
+impl RollingLinReg {
+    #[new]
+    pub fn new(n: usize) -> Result<Self> {
+        Ok(Self { inner: RollingCoMoments::new(n)? })
+    }
+
+    pub fn update(&mut self, y: f64, x: f64) -> (f64, f64, f64) {
+        self.inner.update(x, y);
+        self.value()
+    }
+}
+
+impl Indicator for RollingSum {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.nan_count > 0 {
+            f64::NAN
+        } else {
+            self.sum
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+        self.sum = 0.0;
+    }
+}
+
+impl Indicator for RollingMean {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    // mean of the valid points, NaN only if the window has none
+    fn value(&self) -> f64 {
+        self.sum / (self.container.len() - self.nan_count) as f64
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+        self.sum = 0.0;
+    }
+}
+
+impl Indicator for RollingMax {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.n
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingMin {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.n
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingArgMax {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.bars_since()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.n
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingArgMin {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.bars_since()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.n
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingMinMax {
+    type Input = f64;
+    type Output = (f64, f64, f64, f64);
+
+    fn update(&mut self, input: f64) -> (f64, f64, f64, f64) {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> (f64, f64, f64, f64) {
+        (self.min.value(), self.max.value(), self.min.bars_since(), self.max.bars_since())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.max.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.max.n
+    }
+
+    fn reset(&mut self) {
+        self.min.reset();
+        self.max.reset();
+    }
+}
+
+impl Indicator for RollingQuantile {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.nan_count > 0 {
+            f64::NAN
+        } else {
+            let index = (self.dataset.len() - 1) as f64 * self.quantile;
+            let lower_index = index.floor() as usize;
+            let fraction = index - lower_index as f64;
+
+            let lower_value = self.dataset[lower_index];
+            let upper_value = if lower_index + 1 < self.dataset.len() { self.dataset[lower_index + 1] } else { lower_value };
+
+            lower_value + fraction * (upper_value - lower_value)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.dataset.clear();
+        self.nan_count = self.container.len();
+    }
+}
+
+impl Indicator for RollingRank {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        if self.nan_count > 0 {
+            f64::NAN
+        } else {
+            let last_element = self.container.tail();
+            let count = self.container.iter().filter(|&&x| x < last_element).count();
+            count as f64 / self.container.len() as f64
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.nan_count == 0
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.container.len()
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
+        self.nan_count = self.container.len();
+    }
+}
+
+impl Indicator for RollingVariance {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.moments().map_or(f64::NAN, |m| m.variance(self.ddof))
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingStdev {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.moments().map_or(f64::NAN, |m| m.variance(self.ddof).sqrt())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingSkew {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        match self.inner.moments() {
+            Some(m) if m.n >= 3.0 && !m.is_flat() => {
+                let n = m.n;
+                (n * (n - 1.0)).sqrt() / (n - 2.0) * n.sqrt() * m.m3 / m.m2.powf(1.5)
+            }
+            _ => f64::NAN,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingKurt {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        match self.inner.moments() {
+            Some(m) if m.n >= 4.0 && !m.is_flat() => {
+                let n = m.n;
+                let g2 = n * m.m4 / (m.m2 * m.m2) - 3.0;
+                (n - 1.0) / ((n - 2.0) * (n - 3.0)) * ((n + 1.0) * g2 + 6.0)
+            }
+            _ => f64::NAN,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingZScore {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, input: f64) -> f64 {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> f64 {
+        match self.inner.moments() {
+            Some(m) if m.is_flat() => 0.0,
+            Some(m) => (self.inner.container.tail() - m.mean) / m.variance(self.ddof).sqrt(),
+            None => f64::NAN,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingCovariance {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (x, y): (f64, f64)) -> f64 {
+        Self::update(self, x, y)
+    }
+
+    fn value(&self) -> f64 {
+        match self.inner.moments() {
+            Some(m) if m.n > self.ddof as f64 => m.c_xy / (m.n - self.ddof as f64),
+            _ => f64::NAN,
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingCorrelation {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (x, y): (f64, f64)) -> f64 {
+        Self::update(self, x, y)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.moments().map_or(f64::NAN, |m| m.correlation())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingBeta {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (y, x): (f64, f64)) -> f64 {
+        Self::update(self, y, x)
+    }
+
+    fn value(&self) -> f64 {
+        self.inner.moments().map_or(f64::NAN, |m| m.slope())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl Indicator for RollingLinReg {
+    type Input = (f64, f64);
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, (y, x): (f64, f64)) -> (f64, f64, f64) {
+        Self::update(self, y, x)
+    }
+
+    // r2 is NaN for a flat y, there is nothing to explain
+    fn value(&self) -> (f64, f64, f64) {
+        match self.inner.moments() {
+            Some(m) => {
+                let slope = m.slope();
+                (slope, m.mean_y - slope * m.mean_x, m.correlation().powi(2))
+            }
+            None => (f64::NAN, f64::NAN, f64::NAN),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.inner.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.inner.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl_snapshot!(
+    RollingSum, RollingMean, RollingMax, RollingMin, RollingArgMax, RollingArgMin, RollingMinMax, RollingQuantile, RollingRank, RollingVariance, RollingStdev, RollingSkew, RollingKurt, RollingZScore, RollingCovariance, RollingCorrelation, RollingBeta, RollingLinReg
+);
+impl_batch!(RollingSum, (new_val) -> f64);
+impl_batch!(RollingMean, (new_val) -> f64);
+impl_batch!(RollingMax, (new_val) -> f64);
+impl_batch!(RollingMin, (new_val) -> f64);
+impl_batch!(RollingArgMax, (new_val) -> f64);
+impl_batch!(RollingArgMin, (new_val) -> f64);
+impl_batch!(RollingMinMax, (new_val) -> (0: f64, 1: f64, 2: f64, 3: f64));
+impl_batch!(RollingQuantile, (new_val) -> f64);
+impl_batch!(RollingRank, (new_val) -> f64);
+impl_batch!(RollingVariance, (new_val) -> f64);
+impl_batch!(RollingStdev, (new_val) -> f64);
+impl_batch!(RollingSkew, (new_val) -> f64);
+impl_batch!(RollingKurt, (new_val) -> f64);
+impl_batch!(RollingZScore, (new_val) -> f64);
+impl_batch!(RollingCovariance, (x, y) -> f64);
+impl_batch!(RollingCorrelation, (x, y) -> f64);
+impl_batch!(RollingBeta, (y, x) -> f64);
+impl_batch!(RollingLinReg, (y, x) -> (0: f64, 1: f64, 2: f64));
+impl_indicator!(
+    RollingSum, RollingMean, RollingMax, RollingMin, RollingArgMax, RollingArgMin, RollingMinMax, RollingQuantile, RollingRank, RollingVariance, RollingStdev, RollingSkew, RollingKurt, RollingZScore, RollingCovariance, RollingCorrelation, RollingBeta, RollingLinReg
+);
+
+
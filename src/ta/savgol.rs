@@ -1,31 +1,129 @@
+use super::indicator::{impl_indicator, Indicator};
 use super::rolling::Container;
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
 use nalgebra::{DMatrix, DVector};
+use numpy::{PyArray1, PyReadonlyArray1};
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-// Savgol - Savgol filter
-#[pyclass]
+// Savgol - Savitzky-Golay filter, fits a polyorder polynomial to the window and returns
+// (value, deriv1, ..., deriv<polyorder>) evaluated at pos, where pos=0 is the oldest point
+// and pos=period-1 (default) the newest, same as scipy savgol_coeffs(period, polyorder, deriv, delta, pos, use="dot").
+// with min_periods < period, a shorter window of the newest valid points is fitted during warmup and after NaN.
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Savgol {
     container: Container,
-    derivative1_coeffs: Vec<f64>,
-    derivative2_coeffs: Vec<f64>,
+    polyorder: usize,
+    pos: usize,
+    delta: f64,
+    min_periods: usize,
+    // one row per derivative order, 0 is the smoothed value
+    coeffs: Vec<Vec<f64>>,
 }
 
 #[pymethods]
 impl Savgol {
     #[new]
-    pub fn new(period: usize) -> Self {
-        Self {
-            container: Container::new(period),
-            derivative1_coeffs: savgol_coeffs(period, 2, 1, 1.0),
-            derivative2_coeffs: savgol_coeffs(period, 2, 2, 1.0),
+    #[pyo3(signature = (period, polyorder=2, pos=None, delta=1.0, min_periods=None))]
+    pub fn new(period: usize, polyorder: usize, pos: Option<usize>, delta: f64, min_periods: Option<usize>) -> Result<Self> {
+        let pos = pos.unwrap_or(period.saturating_sub(1));
+        if pos >= period {
+            return Err(Error::InvalidParameter(format!("savgol pos must be less than period {}, got {}", period, pos)));
         }
+        if delta <= 0.0 {
+            return Err(Error::InvalidParameter(format!("savgol delta must be positive, got {}", delta)));
+        }
+        // a shorter window still has to reach pos and determine the polynomial
+        let min_min_periods = (polyorder + 1).max(period - pos);
+        let min_periods = min_periods.unwrap_or(period);
+        if min_periods < min_min_periods || min_periods > period {
+            return Err(Error::InvalidParameter(format!("savgol min_periods must be in [{}, {}], got {}", min_min_periods, period, min_periods)));
+        }
+        let coeffs = (0..=polyorder).map(|deriv| savgol_coeffs(period, polyorder, deriv, delta, pos as f64)).collect::<Result<_>>()?;
+        Ok(Self {
+            container: Container::new(period)?,
+            polyorder,
+            pos,
+            delta,
+            min_periods,
+            coeffs,
+        })
     }
 
-    pub fn update(&mut self, new_val: f64) -> (f64, f64) {
+    pub fn update(&mut self, new_val: f64) -> Vec<f64> {
         self.container.update(new_val);
-        let deriv1 = self.derivative1_coeffs.iter().zip(self.container.iter()).map(|(a, b)| a * b).sum();
-        let deriv2 = self.derivative2_coeffs.iter().zip(self.container.iter()).map(|(a, b)| a * b).sum();
-        (deriv1, deriv2)
+        self.value()
+    }
+
+    // one array per derivative order
+    pub fn batch<'py>(&mut self, py: Python<'py>, new_val: PyReadonlyArray1<'py, f64>) -> Vec<Bound<'py, PyArray1<f64>>> {
+        let new_val = new_val.as_array();
+        let mut columns = vec![Vec::with_capacity(new_val.len()); self.polyorder + 1];
+        for &v in new_val.iter() {
+            for (column, value) in columns.iter_mut().zip(self.update(v)) {
+                column.push(value);
+            }
+        }
+        columns.into_iter().map(|column| PyArray1::from_vec(py, column)).collect()
+    }
+
+    // non-causal filter of a whole series, same as scipy.signal.savgol_filter(x, window_length, polyorder, deriv, delta, mode=mode, cval=cval)
+    #[staticmethod]
+    #[pyo3(signature = (x, window_length, polyorder, deriv=0, delta=1.0, mode="interp", cval=0.0))]
+    pub fn filter<'py>(py: Python<'py>, x: PyReadonlyArray1<'py, f64>, window_length: usize, polyorder: usize, deriv: usize, delta: f64, mode: &str, cval: f64) -> Result<Bound<'py, PyArray1<f64>>> {
+        let x = x.as_array();
+        let y = savgol_filter(&x.to_vec(), window_length, polyorder, deriv, delta, mode, cval)?;
+        Ok(PyArray1::from_vec(py, y))
+    }
+}
+
+impl Savgol {
+    // number of consecutive valid points ending at the newest one
+    fn valid_len(&self) -> usize {
+        let n = self.container.len();
+        (0..n).rev().take_while(|&i| !self.container.get(i).is_nan()).count()
+    }
+}
+
+impl Indicator for Savgol {
+    type Input = f64;
+    type Output = Vec<f64>;
+
+    fn update(&mut self, input: f64) -> Vec<f64> {
+        Self::update(self, input)
+    }
+
+    fn value(&self) -> Vec<f64> {
+        let n = self.container.len();
+        let m = self.valid_len();
+        if m == n {
+            return self.coeffs.iter().map(|coeffs| coeffs.iter().zip(self.container.iter()).map(|(a, b)| a * b).sum()).collect();
+        }
+        if m < self.min_periods {
+            return vec![f64::NAN; self.polyorder + 1];
+        }
+        // refit the newest m points, rare enough to solve on the fly
+        let pos = (self.pos + m - n) as f64;
+        (0..=self.polyorder)
+            .map(|deriv| match savgol_coeffs(m, self.polyorder, deriv, self.delta, pos) {
+                Ok(coeffs) => coeffs.iter().enumerate().map(|(i, a)| a * self.container.get(n - m + i)).sum(),
+                Err(_) => f64::NAN,
+            })
+            .collect()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.valid_len() >= self.min_periods
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.min_periods
+    }
+
+    fn reset(&mut self) {
+        self.container.reset();
     }
 }
 
@@ -34,27 +132,31 @@ fn factorial(n: usize) -> usize {
     (1..=n).product()
 }
 
-/// Compute the Savitzky-Golay filter coefficients at right position.
+/// Compute the Savitzky-Golay filter coefficients for dot product with the window, oldest point first.
 /// visit https://docs.scipy.org/doc/scipy-1.15.0/reference/generated/scipy.signal.savgol_coeffs.html source code for more information.
 ///
 /// # Arguments
 /// * `window_length` - The length of the filter window.
 /// * `polyorder` - The order of the polynomial to fit.
-/// * `deriv` - The derivative to compute (default is 0).
-/// * `delta` - The spacing between samples (default is 1.0).
-/// * `pos` - if pos=0, after dot operation, it will be slope at pos=0; if pos=window_length-1, it will be slope at pos=window_length-1
+/// * `deriv` - The derivative to compute, 0 is the smoothed value.
+/// * `delta` - The spacing between samples.
+/// * `pos` - Where the polynomial is evaluated, 0 is the oldest point and window_length-1 the newest; may be half-integer for even windows
 ///
 /// # Returns
-/// A vector of filter coefficients
-fn savgol_coeffs(window_length: usize, polyorder: usize, deriv: usize, delta: f64) -> Vec<f64> {
-    // polyorder must be less than window_length.
-    assert_eq!(polyorder < window_length, true);
+/// A vector of filter coefficients, or an error if polyorder is not less than window_length
+fn savgol_coeffs(window_length: usize, polyorder: usize, deriv: usize, delta: f64, pos: f64) -> Result<Vec<f64>> {
+    if polyorder >= window_length {
+        return Err(Error::InvalidParameter(format!(
+            "savgol window_length must be greater than polyorder {}, got {}",
+            polyorder, window_length
+        )));
+    }
     if deriv > polyorder {
-        return vec![0.0; window_length];
+        return Ok(vec![0.0; window_length]);
     }
 
-    // Create the special vector(x) at right position
-    let x: Vec<f64> = (1 - window_length as isize..=0).map(|i| i as f64).collect();
+    // sample positions relative to pos
+    let x: Vec<f64> = (0..window_length).map(|i| i as f64 - pos).collect();
     // Form the design matrix (A)
     let mut a = DMatrix::zeros(polyorder + 1, window_length);
 
@@ -69,7 +171,147 @@ fn savgol_coeffs(window_length: usize, polyorder: usize, deriv: usize, delta: f6
     let mut y = DVector::zeros(polyorder + 1);
     y[deriv] = factorial(deriv) as f64 / delta.powi(deriv as i32);
     // Solve the least-squares problem (A * x = y)
-    let coeffs = a.svd(true, true).solve(&y, 1e-8).expect("solve matrix error");
+    let coeffs = a.svd(true, true).solve(&y, 1e-8).map_err(|e| Error::Numeric(format!("savgol least-squares: {}", e)))?;
+
+    Ok(coeffs.as_slice().to_vec())
+}
 
-    coeffs.as_slice().to_vec()
+/// Port of scipy.signal.savgol_filter for a 1-d series.
+/// the window is centered (pos=window_length/2-0.5 for even windows) and the edges are extended by mode:
+/// "mirror", "constant" (cval), "nearest", "wrap", or "interp" which evaluates the polynomial fitted
+/// to the first/last window_length points instead of padding.
+pub fn savgol_filter(x: &[f64], window_length: usize, polyorder: usize, deriv: usize, delta: f64, mode: &str, cval: f64) -> Result<Vec<f64>> {
+    if !matches!(mode, "mirror" | "constant" | "nearest" | "wrap" | "interp") {
+        return Err(Error::InvalidParameter(format!(
+            "savgol mode must be 'mirror', 'constant', 'nearest', 'wrap' or 'interp', got '{}'",
+            mode
+        )));
+    }
+    let n = x.len();
+    if mode == "interp" && window_length > n {
+        return Err(Error::InvalidParameter(format!(
+            "savgol window_length must be less than or equal to the size of x {} with mode 'interp', got {}",
+            n, window_length
+        )));
+    }
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+    let center = if window_length.is_multiple_of(2) {
+        window_length as f64 / 2.0 - 0.5
+    } else {
+        (window_length / 2) as f64
+    };
+    let coeffs = savgol_coeffs(window_length, polyorder, deriv, delta, center)?;
+
+    // value at a possibly out of range index, extended by mode
+    let at = |i: isize| -> f64 {
+        if (0..n as isize).contains(&i) {
+            return x[i as usize];
+        }
+        match mode {
+            "nearest" => x[i.clamp(0, n as isize - 1) as usize],
+            "wrap" => x[i.rem_euclid(n as isize) as usize],
+            "mirror" if n > 1 => {
+                let period = 2 * (n as isize - 1);
+                let j = i.rem_euclid(period);
+                x[if j < n as isize { j } else { period - j } as usize]
+            }
+            "mirror" => x[0],
+            _ => cval,
+        }
+    };
+    let offset = ((window_length - 1) / 2) as isize;
+    let mut y: Vec<f64> = (0..n as isize).map(|i| coeffs.iter().enumerate().map(|(j, c)| c * at(i - offset + j as isize)).sum()).collect();
+
+    if mode == "interp" {
+        let halflen = window_length / 2;
+        let head = &x[..window_length];
+        let tail = &x[n - window_length..];
+        for (i, yi) in y[..halflen].iter_mut().enumerate() {
+            let coeffs = savgol_coeffs(window_length, polyorder, deriv, delta, i as f64)?;
+            *yi = coeffs.iter().zip(head).map(|(c, v)| c * v).sum();
+        }
+        for (i, yi) in y[n - halflen..].iter_mut().enumerate() {
+            let coeffs = savgol_coeffs(window_length, polyorder, deriv, delta, (window_length - halflen + i) as f64)?;
+            *yi = coeffs.iter().zip(tail).map(|(c, v)| c * v).sum();
+        }
+    }
+    Ok(y)
+}
+
+impl_snapshot!(Savgol);
+impl_indicator!(Savgol);
+
+// reference values from scipy.signal: the savgol_filter docstring, test_savgol.py and its even window closed forms
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64], tol: f64) {
+        assert_eq!(actual.len(), expected.len());
+        for (i, (a, e)) in actual.iter().zip(expected).enumerate() {
+            assert!((a - e).abs() <= tol, "index {}: got {}, expected {}", i, a, e);
+        }
+    }
+
+    #[test]
+    fn filter_odd_window_matches_scipy_docstring() {
+        let x = [2.0, 2.0, 5.0, 2.0, 1.0, 0.0, 1.0, 4.0, 9.0];
+        // printed with 2 decimals in the docstring
+        let interp = savgol_filter(&x, 5, 2, 0, 1.0, "interp", 0.0).unwrap();
+        assert_close(&interp, &[1.66, 3.17, 3.54, 2.86, 0.66, 0.17, 1.0, 4.0, 9.0], 5e-3);
+        let nearest = savgol_filter(&x, 5, 2, 0, 1.0, "nearest", 0.0).unwrap();
+        assert_close(&nearest, &[1.74, 3.03, 3.54, 2.86, 0.66, 0.17, 1.0, 4.6, 7.97], 5e-3);
+    }
+
+    #[test]
+    fn filter_odd_window_padding_modes() {
+        let x = [1.0, 2.0, 1.0];
+        let filter = |mode| savgol_filter(&x, 3, 1, 0, 1.0, mode, 0.0).unwrap();
+        assert_close(&filter("constant"), &[1.0, 4.0 / 3.0, 1.0], 1e-12);
+        assert_close(&filter("mirror"), &[5.0 / 3.0, 4.0 / 3.0, 5.0 / 3.0], 1e-12);
+        assert_close(&filter("wrap"), &[4.0 / 3.0, 4.0 / 3.0, 4.0 / 3.0], 1e-12);
+        assert_close(&filter("nearest"), &[4.0 / 3.0, 4.0 / 3.0, 4.0 / 3.0], 1e-12);
+        assert_close(&filter("interp"), &[4.0 / 3.0, 4.0 / 3.0, 4.0 / 3.0], 1e-12);
+    }
+
+    #[test]
+    fn filter_even_window_all_modes() {
+        // polyorder 0 coefficients are 1/window_length, so each output is the mean of x[i-1..=i+2]
+        let x = [2.0, 2.0, 5.0, 2.0, 1.0, 0.0, 1.0, 4.0, 9.0];
+        let cases: [(&str, [f64; 9]); 5] = [
+            ("mirror", [11.0, 11.0, 10.0, 8.0, 4.0, 6.0, 14.0, 18.0, 18.0]),
+            ("constant", [9.0, 11.0, 10.0, 8.0, 4.0, 6.0, 14.0, 14.0, 13.0]),
+            ("nearest", [11.0, 11.0, 10.0, 8.0, 4.0, 6.0, 14.0, 23.0, 31.0]),
+            ("wrap", [18.0, 11.0, 10.0, 8.0, 4.0, 6.0, 14.0, 16.0, 17.0]),
+            ("interp", [11.0, 11.0, 10.0, 8.0, 4.0, 6.0, 14.0, 14.0, 14.0]),
+        ];
+        for (mode, sums) in cases {
+            let expected: Vec<f64> = sums.iter().map(|s| s / 4.0).collect();
+            assert_close(&savgol_filter(&x, 4, 0, 0, 1.0, mode, 0.0).unwrap(), &expected, 1e-12);
+        }
+    }
+
+    #[test]
+    fn coeffs_even_window_closed_forms() {
+        for length in (4..=16).step_by(2) {
+            let m = length as f64 / 2.0;
+            let center = m - 0.5;
+            assert_close(&savgol_coeffs(length, 0, 0, 1.0, center).unwrap(), &vec![1.0 / length as f64; length], 1e-12);
+
+            // oldest point first, k - 0.5 is the distance from the center
+            let ks: Vec<f64> = (0..length).map(|i| i as f64 - m + 1.0).collect();
+            let deriv1: Vec<f64> = ks.iter().map(|k| 6.0 * (k - 0.5) / ((2.0 * m + 1.0) * m * (2.0 * m - 1.0))).collect();
+            assert_close(&savgol_coeffs(length, 1, 1, 1.0, center).unwrap(), &deriv1, 1e-10);
+            assert_close(&savgol_coeffs(length, 2, 1, 1.0, center).unwrap(), &deriv1, 1e-10);
+
+            let deriv2: Vec<f64> = ks
+                .iter()
+                .map(|k| 15.0 * (-4.0 * m * m + 1.0 + 12.0 * (k - 0.5).powi(2)) / (4.0 * (2.0 * m + 1.0) * (m + 1.0) * m * (m - 1.0) * (2.0 * m - 1.0)))
+                .collect();
+            assert_close(&savgol_coeffs(length, 2, 2, 1.0, center).unwrap(), &deriv2, 1e-10);
+            assert_close(&savgol_coeffs(length, 3, 2, 1.0, center).unwrap(), &deriv2, 1e-10);
+        }
+    }
 }
@@ -0,0 +1,494 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
+use super::{
+    ma::MA,
+    rolling::{check_period, Container, RollingSum},
+    volatility::ATR,
+};
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+fn true_range(high: f64, low: f64, preclose: f64) -> f64 {
+    (high - low).max((high - preclose).abs()).max((low - preclose).abs())
+}
+
+// directional index in percent, a zero range is treated as no movement
+fn percent_of(part: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        0.0
+    } else {
+        100.0 * part / total
+    }
+}
+
+// ADX - Average Directional Index with +DI and -DI, rma is Wilder's smoothing
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ADX {
+    plus_dm_ma: MA,
+    minus_dm_ma: MA,
+    tr_ma: MA,
+    adx_ma: MA,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_high: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_low: f64,
+}
+
+#[pymethods]
+impl ADX {
+    #[new]
+    #[pyo3(signature = (ma_period=14, ma_type="rma"))]
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            plus_dm_ma: MA::new(ma_period, ma_type)?,
+            minus_dm_ma: MA::new(ma_period, ma_type)?,
+            tr_ma: MA::new(ma_period, ma_type)?,
+            adx_ma: MA::new(ma_period, ma_type)?,
+            prev_high: f64::NAN,
+            prev_low: f64::NAN,
+        })
+    }
+
+    // returns (+DI, -DI, ADX)
+    pub fn update(&mut self, high: f64, low: f64, preclose: f64) -> (f64, f64, f64) {
+        let up_move = high - self.prev_high;
+        let down_move = self.prev_low - low;
+        self.prev_high = high;
+        self.prev_low = low;
+
+        // the first bar has no directional movement, its true range is skipped too so all mas stay aligned
+        let (plus_dm, minus_dm, tr) = if up_move.is_nan() || down_move.is_nan() {
+            (f64::NAN, f64::NAN, f64::NAN)
+        } else {
+            let plus_dm = if up_move > down_move && up_move > 0.0 { up_move } else { 0.0 };
+            let minus_dm = if down_move > up_move && down_move > 0.0 { down_move } else { 0.0 };
+            (plus_dm, minus_dm, true_range(high, low, preclose))
+        };
+        let tr_avg = self.tr_ma.update(tr);
+        let plus_di = percent_of(self.plus_dm_ma.update(plus_dm), tr_avg);
+        let minus_di = percent_of(self.minus_dm_ma.update(minus_dm), tr_avg);
+        let adx = self.adx_ma.update(percent_of((plus_di - minus_di).abs(), plus_di + minus_di));
+        (plus_di, minus_di, adx)
+    }
+}
+
+// Aroon - bars since the highest high and the lowest low over period, (up, down, oscillator) in percent
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Aroon {
+    // period + 1 bars, head is the oldest
+    highs: Container,
+    lows: Container,
+}
+
+#[pymethods]
+impl Aroon {
+    #[new]
+    #[pyo3(signature = (period=14))]
+    pub fn new(period: usize) -> Result<Self> {
+        check_period(period)?;
+        Ok(Self {
+            highs: Container::new(period + 1)?,
+            lows: Container::new(period + 1)?,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64) -> (f64, f64, f64) {
+        self.highs.update(high);
+        self.lows.update(low);
+        self.value()
+    }
+}
+
+// index of the newest extreme in the window, ties go to the most recent bar like TA-Lib
+fn newest_extreme(container: &Container, is_worse: impl Fn(f64, f64) -> bool) -> usize {
+    let mut best_idx = 0;
+    let mut best = f64::NAN;
+    for (i, &x) in container.iter().enumerate() {
+        if best.is_nan() || !is_worse(x, best) {
+            best = x;
+            best_idx = i;
+        }
+    }
+    best_idx
+}
+
+// PSAR - Parabolic Stop And Reverse, follows TA-Lib SAR
+// the direction is chosen from the directional movement of the first two bars
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PSAR {
+    af_start: f64,
+    af_step: f64,
+    af_max: f64,
+    af: f64,
+    is_long: bool,
+    // sar of the next bar and the extreme point of the current trend
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    sar: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    ep: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_high: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_low: f64,
+    // sar of the current bar
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    psar: f64,
+}
+
+#[pymethods]
+impl PSAR {
+    #[new]
+    #[pyo3(signature = (af_start=0.02, af_step=0.02, af_max=0.2))]
+    pub fn new(af_start: f64, af_step: f64, af_max: f64) -> Self {
+        Self {
+            af_start,
+            af_step,
+            af_max,
+            af: af_start,
+            is_long: true,
+            sar: f64::NAN,
+            ep: f64::NAN,
+            prev_high: f64::NAN,
+            prev_low: f64::NAN,
+            psar: f64::NAN,
+        }
+    }
+
+    // a bar with NaN is skipped and returns NaN
+    pub fn update(&mut self, high: f64, low: f64) -> f64 {
+        if high.is_nan() || low.is_nan() {
+            return f64::NAN;
+        }
+        if self.prev_high.is_nan() {
+            self.prev_high = high;
+            self.prev_low = low;
+            return f64::NAN;
+        }
+        if self.sar.is_nan() {
+            let minus_dm = self.prev_low - low;
+            self.is_long = !(minus_dm > 0.0 && minus_dm > high - self.prev_high);
+            if self.is_long {
+                self.ep = high;
+                self.sar = self.prev_low;
+            } else {
+                self.ep = low;
+                self.sar = self.prev_high;
+            }
+            self.af = self.af_start;
+            self.prev_high = high;
+            self.prev_low = low;
+        }
+
+        let (prev_high, prev_low) = (self.prev_high, self.prev_low);
+        self.prev_high = high;
+        self.prev_low = low;
+        if self.is_long {
+            if low <= self.sar {
+                // reverse to short at the extreme point
+                self.is_long = false;
+                self.psar = self.ep.max(prev_high).max(high);
+                self.af = self.af_start;
+                self.ep = low;
+                self.sar = (self.psar + self.af * (self.ep - self.psar)).max(prev_high).max(high);
+            } else {
+                self.psar = self.sar;
+                if high > self.ep {
+                    self.ep = high;
+                    self.af = (self.af + self.af_step).min(self.af_max);
+                }
+                self.sar = (self.sar + self.af * (self.ep - self.sar)).min(prev_low).min(low);
+            }
+        } else if high >= self.sar {
+            // reverse to long at the extreme point
+            self.is_long = true;
+            self.psar = self.ep.min(prev_low).min(low);
+            self.af = self.af_start;
+            self.ep = high;
+            self.sar = (self.psar + self.af * (self.ep - self.psar)).min(prev_low).min(low);
+        } else {
+            self.psar = self.sar;
+            if low < self.ep {
+                self.ep = low;
+                self.af = (self.af + self.af_step).min(self.af_max);
+            }
+            self.sar = (self.sar + self.af * (self.ep - self.sar)).max(prev_high).max(high);
+        }
+        self.psar
+    }
+}
+
+// SuperTrend - ATR bands around hl2 that only tighten while the trend holds, returns (supertrend, direction)
+// direction is 1 for an uptrend (supertrend is the lower band) and -1 for a downtrend, the first bar starts down
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SuperTrend {
+    atr: ATR,
+    multiplier: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    upper: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    lower: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    direction: f64,
+}
+
+#[pymethods]
+impl SuperTrend {
+    #[new]
+    #[pyo3(signature = (ma_period=10, multiplier=3.0, ma_type="rma"))]
+    pub fn new(ma_period: usize, multiplier: f64, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            atr: ATR::new(ma_period, ma_type)?,
+            multiplier,
+            upper: f64::NAN,
+            lower: f64::NAN,
+            direction: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, preclose: f64) -> (f64, f64) {
+        let atr_val = self.atr.update(high, low, preclose);
+        let hl2 = (high + low) / 2.0;
+        let basic_upper = hl2 + self.multiplier * atr_val;
+        let basic_lower = hl2 - self.multiplier * atr_val;
+        if basic_upper.is_nan() || basic_lower.is_nan() || close.is_nan() {
+            return (f64::NAN, f64::NAN);
+        }
+
+        // a band is only reset once the previous close has crossed it
+        let upper = if self.upper.is_nan() || basic_upper < self.upper || preclose > self.upper { basic_upper } else { self.upper };
+        let lower = if self.lower.is_nan() || basic_lower > self.lower || preclose < self.lower { basic_lower } else { self.lower };
+        self.direction = if self.direction == 1.0 {
+            if close < lower {
+                -1.0
+            } else {
+                1.0
+            }
+        } else if close > upper {
+            1.0
+        } else {
+            -1.0
+        };
+        self.upper = upper;
+        self.lower = lower;
+        self.value()
+    }
+}
+
+// Vortex - VI+ and VI-, upward and downward vortex movement over the summed true range
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Vortex {
+    plus_vm_sum: RollingSum,
+    minus_vm_sum: RollingSum,
+    tr_sum: RollingSum,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_high: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_low: f64,
+}
+
+#[pymethods]
+impl Vortex {
+    #[new]
+    #[pyo3(signature = (period=14))]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            plus_vm_sum: RollingSum::new(period)?,
+            minus_vm_sum: RollingSum::new(period)?,
+            tr_sum: RollingSum::new(period)?,
+            prev_high: f64::NAN,
+            prev_low: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, preclose: f64) -> (f64, f64) {
+        let plus_vm = (high - self.prev_low).abs();
+        let minus_vm = (low - self.prev_high).abs();
+        self.prev_high = high;
+        self.prev_low = low;
+        // the first bar has no vortex movement, its true range is skipped too
+        let tr = if plus_vm.is_nan() || minus_vm.is_nan() { f64::NAN } else { true_range(high, low, preclose) };
+        self.plus_vm_sum.update(plus_vm);
+        self.minus_vm_sum.update(minus_vm);
+        self.tr_sum.update(tr);
+        self.value()
+    }
+}
+
+// like EMA, a NaN bar returns NaN while value() keeps the last averages
+impl Indicator for ADX {
+    type Input = (f64, f64, f64);
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, (high, low, preclose): (f64, f64, f64)) -> (f64, f64, f64) {
+        Self::update(self, high, low, preclose)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        let tr_avg = self.tr_ma.value();
+        let plus_di = percent_of(self.plus_dm_ma.value(), tr_avg);
+        let minus_di = percent_of(self.minus_dm_ma.value(), tr_avg);
+        (plus_di, minus_di, self.adx_ma.value())
+    }
+
+    fn is_ready(&self) -> bool {
+        self.tr_ma.is_ready() && self.plus_dm_ma.is_ready() && self.minus_dm_ma.is_ready() && self.adx_ma.is_ready()
+    }
+
+    // the first bar has no directional movement
+    fn warmup_period(&self) -> usize {
+        self.tr_ma.warmup_period() + self.adx_ma.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.plus_dm_ma.reset();
+        self.minus_dm_ma.reset();
+        self.tr_ma.reset();
+        self.adx_ma.reset();
+        self.prev_high = f64::NAN;
+        self.prev_low = f64::NAN;
+    }
+}
+
+impl Indicator for Aroon {
+    type Input = (f64, f64);
+    type Output = (f64, f64, f64);
+
+    fn update(&mut self, (high, low): (f64, f64)) -> (f64, f64, f64) {
+        Self::update(self, high, low)
+    }
+
+    fn value(&self) -> (f64, f64, f64) {
+        if !self.is_ready() {
+            return (f64::NAN, f64::NAN, f64::NAN);
+        }
+        let period = (self.highs.len() - 1) as f64;
+        // index period is the newest bar, so an extreme there is 100
+        let up = 100.0 * newest_extreme(&self.highs, |x, best| x < best) as f64 / period;
+        let down = 100.0 * newest_extreme(&self.lows, |x, best| x > best) as f64 / period;
+        (up, down, up - down)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.highs.is_valid() && self.lows.is_valid()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.highs.len()
+    }
+
+    fn reset(&mut self) {
+        self.highs.reset();
+        self.lows.reset();
+    }
+}
+
+impl Indicator for PSAR {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low): (f64, f64)) -> f64 {
+        Self::update(self, high, low)
+    }
+
+    fn value(&self) -> f64 {
+        self.psar
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.psar.is_nan()
+    }
+
+    fn warmup_period(&self) -> usize {
+        2
+    }
+
+    fn reset(&mut self) {
+        self.af = self.af_start;
+        self.is_long = true;
+        self.sar = f64::NAN;
+        self.ep = f64::NAN;
+        self.prev_high = f64::NAN;
+        self.prev_low = f64::NAN;
+        self.psar = f64::NAN;
+    }
+}
+
+impl Indicator for SuperTrend {
+    type Input = (f64, f64, f64, f64);
+    type Output = (f64, f64);
+
+    fn update(&mut self, (high, low, close, preclose): (f64, f64, f64, f64)) -> (f64, f64) {
+        Self::update(self, high, low, close, preclose)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        if self.direction == 1.0 {
+            (self.lower, 1.0)
+        } else if self.direction == -1.0 {
+            (self.upper, -1.0)
+        } else {
+            (f64::NAN, f64::NAN)
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.atr.is_ready() && !self.direction.is_nan()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.atr.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.atr.reset();
+        self.upper = f64::NAN;
+        self.lower = f64::NAN;
+        self.direction = f64::NAN;
+    }
+}
+
+impl Indicator for Vortex {
+    type Input = (f64, f64, f64);
+    type Output = (f64, f64);
+
+    fn update(&mut self, (high, low, preclose): (f64, f64, f64)) -> (f64, f64) {
+        Self::update(self, high, low, preclose)
+    }
+
+    fn value(&self) -> (f64, f64) {
+        let tr = self.tr_sum.value();
+        (self.plus_vm_sum.value() / tr, self.minus_vm_sum.value() / tr)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.plus_vm_sum.is_ready() && self.minus_vm_sum.is_ready() && self.tr_sum.is_ready()
+    }
+
+    // the first bar has no vortex movement
+    fn warmup_period(&self) -> usize {
+        self.tr_sum.warmup_period() + 1
+    }
+
+    fn reset(&mut self) {
+        self.plus_vm_sum.reset();
+        self.minus_vm_sum.reset();
+        self.tr_sum.reset();
+        self.prev_high = f64::NAN;
+        self.prev_low = f64::NAN;
+    }
+}
+
+impl_snapshot!(ADX, Aroon, PSAR, SuperTrend, Vortex);
+impl_batch!(ADX, (high, low, preclose) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(Aroon, (high, low) -> (0: f64, 1: f64, 2: f64));
+impl_batch!(PSAR, (high, low) -> f64);
+impl_batch!(SuperTrend, (high, low, close, preclose) -> (0: f64, 1: f64));
+impl_batch!(Vortex, (high, low, preclose) -> (0: f64, 1: f64));
+impl_indicator!(ADX, Aroon, PSAR, SuperTrend, Vortex);
@@ -1,8 +1,17 @@
+use super::batch::impl_batch;
+use super::indicator::{impl_indicator, Indicator};
 use super::ma::MA;
+use super::rolling::{Container, RollingSum, RollingVariance};
+use crate::broker::analyzer::CN_TRADING_DAYS;
+use crate::error::{Error, Result};
+use crate::snapshot::impl_snapshot;
+use nalgebra::{Matrix3, Vector3};
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
 // ATR - Average True Range
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ATR {
     smooth_ma: MA,
 }
@@ -11,10 +20,10 @@ pub struct ATR {
 impl ATR {
     #[new]
     #[pyo3(signature = (ma_period=21, ma_type="rma"))]
-    pub fn new(ma_period: usize, ma_type: &str) -> Self {
-        Self {
-            smooth_ma: MA::new(ma_period, ma_type),
-        }
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            smooth_ma: MA::new(ma_period, ma_type)?,
+        })
     }
 
     pub fn update(&mut self, high: f64, low: f64, preclose: f64) -> f64 {
@@ -24,21 +33,575 @@ impl ATR {
 }
 
 // NATR - Normalized Average True Range
-#[pyclass]
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct NATR {
     atr: ATR,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    close: f64,
 }
 
 #[pymethods]
 impl NATR {
     #[new]
     #[pyo3(signature = (ma_period=21, ma_type="rma"))]
-    pub fn new(ma_period: usize, ma_type: &str) -> Self {
-        Self { atr: ATR::new(ma_period, ma_type) }
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            atr: ATR::new(ma_period, ma_type)?,
+            close: f64::NAN,
+        })
     }
 
     pub fn update(&mut self, high: f64, low: f64, close: f64, preclose: f64) -> f64 {
+        self.close = close;
         let atr_val = self.atr.update(high, low, preclose);
         atr_val / close
     }
 }
+
+// the estimators below return volatility annualized by periods_per_year, 1.0 keeps it per bar
+
+// Parkinson - high/low range estimator, ln(high / low)^2 / (4 ln 2) averaged over period
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Parkinson {
+    sum: RollingSum,
+    periods_per_year: f64,
+}
+
+#[pymethods]
+impl Parkinson {
+    #[new]
+    #[pyo3(signature = (period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(period: usize, periods_per_year: f64) -> Result<Self> {
+        Ok(Self {
+            sum: RollingSum::new(period)?,
+            periods_per_year,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64) -> f64 {
+        self.sum.update((high / low).ln().powi(2) / (4.0 * std::f64::consts::LN_2));
+        self.value()
+    }
+}
+
+// GarmanKlass - open/high/low/close estimator, 0.5 ln(high / low)^2 - (2 ln 2 - 1) ln(close / open)^2,
+// ignores the opening jump
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GarmanKlass {
+    sum: RollingSum,
+    periods_per_year: f64,
+}
+
+#[pymethods]
+impl GarmanKlass {
+    #[new]
+    #[pyo3(signature = (period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(period: usize, periods_per_year: f64) -> Result<Self> {
+        Ok(Self {
+            sum: RollingSum::new(period)?,
+            periods_per_year,
+        })
+    }
+
+    pub fn update(&mut self, open: f64, high: f64, low: f64, close: f64) -> f64 {
+        let range = (high / low).ln();
+        let body = (close / open).ln();
+        self.sum.update(0.5 * range * range - (2.0 * std::f64::consts::LN_2 - 1.0) * body * body);
+        self.value()
+    }
+}
+
+// ln(high / close) ln(high / open) + ln(low / close) ln(low / open), unbiased under drift
+fn rogers_satchell(open: f64, high: f64, low: f64, close: f64) -> f64 {
+    (high / close).ln() * (high / open).ln() + (low / close).ln() * (low / open).ln()
+}
+
+// a sample variance (ddof = 1) of a single point is NaN forever
+fn check_sample_period(period: usize) -> Result<()> {
+    if period < 2 {
+        return Err(Error::InvalidParameter(format!("period must be at least 2 for a sample variance, got {}", period)));
+    }
+    Ok(())
+}
+
+// RogersSatchell - drift independent open/high/low/close estimator
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RogersSatchell {
+    sum: RollingSum,
+    periods_per_year: f64,
+}
+
+#[pymethods]
+impl RogersSatchell {
+    #[new]
+    #[pyo3(signature = (period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(period: usize, periods_per_year: f64) -> Result<Self> {
+        Ok(Self {
+            sum: RollingSum::new(period)?,
+            periods_per_year,
+        })
+    }
+
+    pub fn update(&mut self, open: f64, high: f64, low: f64, close: f64) -> f64 {
+        self.sum.update(rogers_satchell(open, high, low, close));
+        self.value()
+    }
+}
+
+// YangZhang - overnight variance + k open-to-close variance + (1 - k) Rogers-Satchell,
+// k = 0.34 / (1.34 + (period + 1) / (period - 1)) minimizes the estimator variance
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct YangZhang {
+    overnight_var: RollingVariance,
+    open_close_var: RollingVariance,
+    rs_sum: RollingSum,
+    k: f64,
+    periods_per_year: f64,
+}
+
+#[pymethods]
+impl YangZhang {
+    #[new]
+    #[pyo3(signature = (period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(period: usize, periods_per_year: f64) -> Result<Self> {
+        check_sample_period(period)?;
+        let n = period as f64;
+        Ok(Self {
+            overnight_var: RollingVariance::new(period, 1)?,
+            open_close_var: RollingVariance::new(period, 1)?,
+            rs_sum: RollingSum::new(period)?,
+            k: 0.34 / (1.34 + (n + 1.0) / (n - 1.0)),
+            periods_per_year,
+        })
+    }
+
+    pub fn update(&mut self, open: f64, high: f64, low: f64, close: f64, preclose: f64) -> f64 {
+        let overnight = (open / preclose).ln();
+        let open_close = (close / open).ln();
+        let rs = rogers_satchell(open, high, low, close);
+        // a bad bar is NaN in all three windows so they stay aligned
+        if overnight.is_finite() && open_close.is_finite() && rs.is_finite() {
+            self.overnight_var.update(overnight);
+            self.open_close_var.update(open_close);
+            self.rs_sum.update(rs);
+        } else {
+            self.overnight_var.update(f64::NAN);
+            self.open_close_var.update(f64::NAN);
+            self.rs_sum.update(f64::NAN);
+        }
+        self.value()
+    }
+}
+
+// HistoricalVolatility - close-to-close volatility, sample stdev of ln(close / preclose)
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoricalVolatility {
+    var: RollingVariance,
+    periods_per_year: f64,
+}
+
+#[pymethods]
+impl HistoricalVolatility {
+    #[new]
+    #[pyo3(signature = (period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(period: usize, periods_per_year: f64) -> Result<Self> {
+        check_sample_period(period)?;
+        Ok(Self {
+            var: RollingVariance::new(period, 1)?,
+            periods_per_year,
+        })
+    }
+
+    pub fn update(&mut self, close: f64, preclose: f64) -> f64 {
+        self.var.update((close / preclose).ln());
+        self.value()
+    }
+}
+
+// GARCH - GARCH(1,1) forecast of next bar volatility from zero mean log returns,
+// h[t+1] = omega + alpha r[t]^2 + beta h[t], refitted by maximum likelihood on the last window returns
+// every refit_period bars, e.g. target_vol / value() scales a position to a volatility target
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GARCH {
+    returns: Container,
+    count: usize,
+    refit_period: usize,
+    since_fit: usize,
+    periods_per_year: f64,
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    omega: f64,
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    alpha: f64,
+    #[pyo3(get)]
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    beta: f64,
+    // per bar variance forecast of the next return
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    variance: f64,
+}
+
+#[pymethods]
+impl GARCH {
+    #[new]
+    #[pyo3(signature = (window=500, refit_period=20, periods_per_year=CN_TRADING_DAYS))]
+    pub fn new(window: usize, refit_period: usize, periods_per_year: f64) -> Result<Self> {
+        if window < 10 {
+            return Err(Error::InvalidParameter(format!("garch window must be at least 10, got {}", window)));
+        }
+        if refit_period == 0 {
+            return Err(Error::InvalidParameter("garch refit_period must be positive".to_string()));
+        }
+        Ok(Self {
+            returns: Container::new(window)?,
+            count: 0,
+            refit_period,
+            since_fit: 0,
+            periods_per_year,
+            omega: f64::NAN,
+            alpha: f64::NAN,
+            beta: f64::NAN,
+            variance: f64::NAN,
+        })
+    }
+
+    // a bar without a valid return is skipped: it returns NaN and keeps the forecast
+    pub fn update(&mut self, close: f64, preclose: f64) -> f64 {
+        let r = (close / preclose).ln();
+        if !r.is_finite() {
+            return f64::NAN;
+        }
+        self.returns.update(r);
+        self.count = (self.count + 1).min(self.returns.len());
+        self.variance = self.omega + self.alpha * r * r + self.beta * self.variance;
+
+        self.since_fit += 1;
+        if self.count == self.returns.len() && (self.variance.is_nan() || self.since_fit >= self.refit_period) {
+            self.since_fit = 0;
+            self.fit();
+        }
+        self.value()
+    }
+}
+
+impl GARCH {
+    // keeps the previous parameters if the window cannot be fitted, e.g. all returns are zero
+    fn fit(&mut self) {
+        let returns: Vec<f64> = self.returns.iter().copied().collect();
+        let scale = returns.iter().map(|r| r * r).sum::<f64>() / returns.len() as f64;
+        if scale <= 0.0 {
+            return;
+        }
+        // fit on returns standardized to unit variance, omega scales back by the variance
+        let standardized: Vec<f64> = returns.iter().map(|r| r / scale.sqrt()).collect();
+        let Some((omega, alpha, beta)) = fit_garch(&standardized) else {
+            return;
+        };
+        self.omega = omega * scale;
+        self.alpha = alpha;
+        self.beta = beta;
+        self.variance = returns.iter().fold(scale, |h, r| self.omega + self.alpha * r * r + self.beta * h);
+    }
+}
+
+// unconstrained u to (omega, alpha, beta) with omega > 0, alpha, beta > 0 and alpha + beta < 1
+fn garch_params(u: &Vector3<f64>) -> (f64, f64, f64) {
+    let (ea, eb) = (u[1].exp(), u[2].exp());
+    let d = 1.0 + ea + eb;
+    (u[0].exp(), ea / d, eb / d)
+}
+
+// negative gaussian log-likelihood without constants, the recursion starts at the sample variance 1
+fn garch_nll(returns: &[f64], u: &Vector3<f64>) -> f64 {
+    let (omega, alpha, beta) = garch_params(u);
+    let mut h = 1.0_f64;
+    let mut nll = 0.0;
+    for r in returns {
+        nll += h.ln() + r * r / h;
+        h = omega + alpha * r * r + beta * h;
+    }
+    0.5 * nll
+}
+
+fn garch_gradient(returns: &[f64], u: &Vector3<f64>) -> Vector3<f64> {
+    let step = 1e-5;
+    Vector3::from_fn(|i, _| {
+        let mut e = Vector3::zeros();
+        e[i] = step;
+        (garch_nll(returns, &(u + e)) - garch_nll(returns, &(u - e))) / (2.0 * step)
+    })
+}
+
+// BFGS with a backtracking line search from alpha = 0.05, beta = 0.9 on unit variance returns
+fn fit_garch(returns: &[f64]) -> Option<(f64, f64, f64)> {
+    let (alpha, beta) = (0.05_f64, 0.9_f64);
+    let rest = 1.0 - alpha - beta;
+    let mut u = Vector3::new(rest.ln(), (alpha / rest).ln(), (beta / rest).ln());
+    let mut f = garch_nll(returns, &u);
+    let mut g = garch_gradient(returns, &u);
+    let mut h_inv = Matrix3::identity();
+
+    for _ in 0..200 {
+        if g.norm() < 1e-6 {
+            break;
+        }
+        let p = -(h_inv * g);
+        let slope = g.dot(&p);
+        let mut t = 1.0;
+        let (u_next, f_next) = loop {
+            let u_next = u + p * t;
+            let f_next = garch_nll(returns, &u_next);
+            if f_next.is_finite() && f_next <= f + 1e-4 * t * slope {
+                break (u_next, f_next);
+            }
+            t *= 0.5;
+            if t < 1e-10 {
+                return f.is_finite().then(|| garch_params(&u));
+            }
+        };
+        let g_next = garch_gradient(returns, &u_next);
+        let s = u_next - u;
+        let y = g_next - g;
+        let sy = s.dot(&y);
+        if sy > 1e-12 {
+            let rho = 1.0 / sy;
+            let left = Matrix3::identity() - s * y.transpose() * rho;
+            h_inv = left * h_inv * left.transpose() + s * s.transpose() * rho;
+        }
+        u = u_next;
+        f = f_next;
+        g = g_next;
+    }
+    f.is_finite().then(|| garch_params(&u))
+}
+
+impl Indicator for ATR {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, preclose): (f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        self.smooth_ma.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.smooth_ma.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.smooth_ma.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.smooth_ma.reset();
+    }
+}
+
+impl Indicator for NATR {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close, preclose): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        self.atr.value() / self.close
+    }
+
+    fn is_ready(&self) -> bool {
+        self.atr.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.atr.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.atr.reset();
+        self.close = f64::NAN;
+    }
+}
+
+impl Indicator for Parkinson {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low): (f64, f64)) -> f64 {
+        Self::update(self, high, low)
+    }
+
+    fn value(&self) -> f64 {
+        (self.sum.value() / self.sum.warmup_period() as f64 * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.sum.reset();
+    }
+}
+
+impl Indicator for GarmanKlass {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (open, high, low, close): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, open, high, low, close)
+    }
+
+    fn value(&self) -> f64 {
+        (self.sum.value() / self.sum.warmup_period() as f64 * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.sum.reset();
+    }
+}
+
+impl Indicator for RogersSatchell {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (open, high, low, close): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, open, high, low, close)
+    }
+
+    fn value(&self) -> f64 {
+        (self.sum.value() / self.sum.warmup_period() as f64 * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.sum.reset();
+    }
+}
+
+impl Indicator for YangZhang {
+    type Input = (f64, f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (open, high, low, close, preclose): (f64, f64, f64, f64, f64)) -> f64 {
+        Self::update(self, open, high, low, close, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        let rs = self.rs_sum.value() / self.rs_sum.warmup_period() as f64;
+        let variance = self.overnight_var.value() + self.k * self.open_close_var.value() + (1.0 - self.k) * rs;
+        (variance * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.rs_sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.rs_sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.overnight_var.reset();
+        self.open_close_var.reset();
+        self.rs_sum.reset();
+    }
+}
+
+impl Indicator for HistoricalVolatility {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (close, preclose): (f64, f64)) -> f64 {
+        Self::update(self, close, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        (self.var.value() * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.var.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.var.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.var.reset();
+    }
+}
+
+impl Indicator for GARCH {
+    type Input = (f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (close, preclose): (f64, f64)) -> f64 {
+        Self::update(self, close, preclose)
+    }
+
+    fn value(&self) -> f64 {
+        (self.variance * self.periods_per_year).sqrt()
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.variance.is_nan()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.returns.len()
+    }
+
+    fn reset(&mut self) {
+        self.returns.reset();
+        self.count = 0;
+        self.since_fit = 0;
+        self.omega = f64::NAN;
+        self.alpha = f64::NAN;
+        self.beta = f64::NAN;
+        self.variance = f64::NAN;
+    }
+}
+
+impl_snapshot!(ATR, NATR, Parkinson, GarmanKlass, RogersSatchell, YangZhang, HistoricalVolatility, GARCH);
+impl_batch!(ATR, (high, low, preclose) -> f64);
+impl_batch!(NATR, (high, low, close, preclose) -> f64);
+impl_batch!(Parkinson, (high, low) -> f64);
+impl_batch!(GarmanKlass, (open, high, low, close) -> f64);
+impl_batch!(RogersSatchell, (open, high, low, close) -> f64);
+impl_batch!(YangZhang, (open, high, low, close, preclose) -> f64);
+impl_batch!(HistoricalVolatility, (close, preclose) -> f64);
+impl_batch!(GARCH, (close, preclose) -> f64);
+impl_indicator!(ATR, NATR, Parkinson, GarmanKlass, RogersSatchell, YangZhang, HistoricalVolatility, GARCH);
+
@@ -0,0 +1,439 @@
+use super::batch::{check_len, impl_batch};
+use super::indicator::{impl_indicator, Indicator};
+use super::{ma::MA, rolling::RollingSum};
+use crate::error::Result;
+use crate::snapshot::impl_snapshot;
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// close location value in [-1, 1]: 1 closes at the high, -1 at the low, 0 for a bar without range
+fn close_location(high: f64, low: f64, close: f64) -> f64 {
+    let range = high - low;
+    if range == 0.0 {
+        0.0
+    } else {
+        ((close - low) - (high - close)) / range
+    }
+}
+
+// cumulative indicators skip a NaN bar: it returns NaN and value() keeps the running total
+
+// OBV - On-Balance Volume, volume added on up closes and subtracted on down closes
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OBV {
+    obv: Option<f64>,
+}
+
+#[pymethods]
+impl OBV {
+    #[new]
+    pub fn new() -> Self {
+        Self { obv: None }
+    }
+
+    pub fn update(&mut self, close: f64, preclose: f64, volume: f64) -> f64 {
+        // NaN comparisons are false, so check the inputs themselves
+        if close.is_nan() || preclose.is_nan() || !volume.is_finite() {
+            return f64::NAN;
+        }
+        let signed_volume = if close > preclose {
+            volume
+        } else if close < preclose {
+            -volume
+        } else {
+            0.0
+        };
+        self.obv = Some(self.obv.unwrap_or(0.0) + signed_volume);
+        self.value()
+    }
+}
+
+// MFI - Money Flow Index in [0, 100], a volume weighted RSI of the typical price
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MFI {
+    positive_sum: RollingSum,
+    negative_sum: RollingSum,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_tp: f64,
+}
+
+#[pymethods]
+impl MFI {
+    #[new]
+    #[pyo3(signature = (period=14))]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            positive_sum: RollingSum::new(period)?,
+            negative_sum: RollingSum::new(period)?,
+            prev_tp: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, volume: f64) -> f64 {
+        let tp = (high + low + close) / 3.0;
+        let money_flow = tp * volume;
+        // the first bar has no previous typical price
+        let (positive, negative) = if tp.is_nan() || self.prev_tp.is_nan() {
+            (f64::NAN, f64::NAN)
+        } else if tp > self.prev_tp {
+            (money_flow, 0.0)
+        } else if tp < self.prev_tp {
+            (0.0, money_flow)
+        } else {
+            (0.0, 0.0)
+        };
+        self.prev_tp = tp;
+        self.positive_sum.update(positive);
+        self.negative_sum.update(negative);
+        self.value()
+    }
+}
+
+// AD - Accumulation/Distribution line, running total of volume weighted by the close location
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AD {
+    ad: Option<f64>,
+}
+
+#[pymethods]
+impl AD {
+    #[new]
+    pub fn new() -> Self {
+        Self { ad: None }
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, volume: f64) -> f64 {
+        let money_flow_volume = close_location(high, low, close) * volume;
+        if !money_flow_volume.is_finite() {
+            return f64::NAN;
+        }
+        self.ad = Some(self.ad.unwrap_or(0.0) + money_flow_volume);
+        self.value()
+    }
+}
+
+// CMF - Chaikin Money Flow in [-1, 1], money flow volume over volume summed over period
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CMF {
+    mfv_sum: RollingSum,
+    volume_sum: RollingSum,
+}
+
+#[pymethods]
+impl CMF {
+    #[new]
+    #[pyo3(signature = (period=20))]
+    pub fn new(period: usize) -> Result<Self> {
+        Ok(Self {
+            mfv_sum: RollingSum::new(period)?,
+            volume_sum: RollingSum::new(period)?,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, close: f64, volume: f64) -> f64 {
+        let money_flow_volume = close_location(high, low, close) * volume;
+        // keep both sums over the same bars
+        let volume = if money_flow_volume.is_nan() { f64::NAN } else { volume };
+        self.mfv_sum.update(money_flow_volume);
+        self.volume_sum.update(volume);
+        self.value()
+    }
+}
+
+// ForceIndex - Elder's Force Index, price change times volume smoothed by a ma
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForceIndex {
+    ma: MA,
+}
+
+#[pymethods]
+impl ForceIndex {
+    #[new]
+    #[pyo3(signature = (ma_period=13, ma_type="ema"))]
+    pub fn new(ma_period: usize, ma_type: &str) -> Result<Self> {
+        Ok(Self {
+            ma: MA::new(ma_period, ma_type)?,
+        })
+    }
+
+    pub fn update(&mut self, close: f64, preclose: f64, volume: f64) -> f64 {
+        self.ma.update((close - preclose) * volume)
+    }
+}
+
+// EaseOfMovement - midpoint move per volume over the bar range, smoothed by a ma
+// volume is divided by divisor to keep the values readable, e.g. 1e8 for shares
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EaseOfMovement {
+    ma: MA,
+    divisor: f64,
+    #[serde(with = "crate::snapshot::nan_as_null")]
+    prev_mid: f64,
+}
+
+#[pymethods]
+impl EaseOfMovement {
+    #[new]
+    #[pyo3(signature = (ma_period=14, ma_type="sma", divisor=1e8))]
+    pub fn new(ma_period: usize, ma_type: &str, divisor: f64) -> Result<Self> {
+        Ok(Self {
+            ma: MA::new(ma_period, ma_type)?,
+            divisor,
+            prev_mid: f64::NAN,
+        })
+    }
+
+    pub fn update(&mut self, high: f64, low: f64, volume: f64) -> f64 {
+        let mid = (high + low) / 2.0;
+        let distance = mid - self.prev_mid;
+        self.prev_mid = mid;
+        // distance / (volume / divisor / range), a bar without volume has no ease of movement
+        let emv = if volume == 0.0 { f64::NAN } else { distance * (high - low) * self.divisor / volume };
+        self.ma.update(emv)
+    }
+}
+
+// AnchoredVWAP - volume weighted average price since the last anchor
+// anchor=true restarts the average at this bar, e.g. the first bar of a month or after an event date
+#[pyclass(module = "bktrader.ta")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AnchoredVWAP {
+    pv_sum: f64,
+    volume_sum: f64,
+}
+
+#[pymethods]
+impl AnchoredVWAP {
+    #[new]
+    pub fn new() -> Self {
+        Self { pv_sum: 0.0, volume_sum: 0.0 }
+    }
+
+    #[pyo3(signature = (price, volume, anchor=false))]
+    pub fn update(&mut self, price: f64, volume: f64, anchor: bool) -> f64 {
+        if anchor {
+            self.pv_sum = 0.0;
+            self.volume_sum = 0.0;
+        }
+        let pv = price * volume;
+        if !pv.is_finite() {
+            return f64::NAN;
+        }
+        self.pv_sum += pv;
+        self.volume_sum += volume;
+        self.value()
+    }
+
+    // the anchor column is a float mask so boolean columns arrive as 0/1, non-zero and not NaN restarts the average
+    pub fn batch<'py>(&mut self, py: Python<'py>, price: PyReadonlyArray1<'py, f64>, volume: PyReadonlyArray1<'py, f64>, anchor: PyReadonlyArray1<'py, f64>) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        let (price, volume, anchor) = (price.as_array(), volume.as_array(), anchor.as_array());
+        let n = check_len(&[&price, &volume, &anchor])?;
+        let column: Vec<f64> = (0..n).map(|i| self.update(price[i], volume[i], anchor[i] != 0.0 && !anchor[i].is_nan())).collect();
+        Ok(PyArray1::from_vec(py, column))
+    }
+}
+
+impl Indicator for OBV {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (close, preclose, volume): (f64, f64, f64)) -> f64 {
+        Self::update(self, close, preclose, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.obv.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.obv.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.obv = None;
+    }
+}
+
+impl Indicator for MFI {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close, volume): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close, volume)
+    }
+
+    fn value(&self) -> f64 {
+        let positive = self.positive_sum.value();
+        let total = positive + self.negative_sum.value();
+        if total == 0.0 {
+            50.0
+        } else {
+            100.0 * positive / total
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        self.positive_sum.is_ready() && self.negative_sum.is_ready()
+    }
+
+    // the first bar has no money flow direction
+    fn warmup_period(&self) -> usize {
+        self.positive_sum.warmup_period() + 1
+    }
+
+    fn reset(&mut self) {
+        self.positive_sum.reset();
+        self.negative_sum.reset();
+        self.prev_tp = f64::NAN;
+    }
+}
+
+impl Indicator for AD {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close, volume): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.ad.unwrap_or(f64::NAN)
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ad.is_some()
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.ad = None;
+    }
+}
+
+impl Indicator for CMF {
+    type Input = (f64, f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, close, volume): (f64, f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, close, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.mfv_sum.value() / self.volume_sum.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.mfv_sum.is_ready() && self.volume_sum.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.volume_sum.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.mfv_sum.reset();
+        self.volume_sum.reset();
+    }
+}
+
+impl Indicator for ForceIndex {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (close, preclose, volume): (f64, f64, f64)) -> f64 {
+        Self::update(self, close, preclose, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.ma.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ma.is_ready()
+    }
+
+    fn warmup_period(&self) -> usize {
+        self.ma.warmup_period()
+    }
+
+    fn reset(&mut self) {
+        self.ma.reset();
+    }
+}
+
+impl Indicator for EaseOfMovement {
+    type Input = (f64, f64, f64);
+    type Output = f64;
+
+    fn update(&mut self, (high, low, volume): (f64, f64, f64)) -> f64 {
+        Self::update(self, high, low, volume)
+    }
+
+    fn value(&self) -> f64 {
+        self.ma.value()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.ma.is_ready()
+    }
+
+    // the first bar has no midpoint move
+    fn warmup_period(&self) -> usize {
+        self.ma.warmup_period() + 1
+    }
+
+    fn reset(&mut self) {
+        self.ma.reset();
+        self.prev_mid = f64::NAN;
+    }
+}
+
+impl Indicator for AnchoredVWAP {
+    type Input = (f64, f64, bool);
+    type Output = f64;
+
+    fn update(&mut self, (price, volume, anchor): (f64, f64, bool)) -> f64 {
+        Self::update(self, price, volume, anchor)
+    }
+
+    // NaN until some volume is traded since the anchor
+    fn value(&self) -> f64 {
+        self.pv_sum / self.volume_sum
+    }
+
+    fn is_ready(&self) -> bool {
+        self.volume_sum > 0.0
+    }
+
+    fn warmup_period(&self) -> usize {
+        1
+    }
+
+    fn reset(&mut self) {
+        self.pv_sum = 0.0;
+        self.volume_sum = 0.0;
+    }
+}
+
+impl_snapshot!(OBV, MFI, AD, CMF, ForceIndex, EaseOfMovement, AnchoredVWAP);
+impl_batch!(OBV, (close, preclose, volume) -> f64);
+impl_batch!(MFI, (high, low, close, volume) -> f64);
+impl_batch!(AD, (high, low, close, volume) -> f64);
+impl_batch!(CMF, (high, low, close, volume) -> f64);
+impl_batch!(ForceIndex, (close, preclose, volume) -> f64);
+impl_batch!(EaseOfMovement, (high, low, volume) -> f64);
+impl_indicator!(OBV, MFI, AD, CMF, ForceIndex, EaseOfMovement, AnchoredVWAP);